@@ -1,6 +1,7 @@
 //! Core language.
 
 use std::fmt;
+use std::hash::{Hash, Hasher};
 
 use crate::env::{Index, Level};
 use crate::source::Span;
@@ -33,7 +34,7 @@ pub enum Item<'arena> {
 /// inserting [metavariables][Term::InsertedMeta] during elaboration.
 //
 // See also: https://en.wikipedia.org/wiki/Abstract_and_concrete
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum LocalInfo {
     /// The entry was bound as a definition in the environment.
     Def,
@@ -41,7 +42,7 @@ pub enum LocalInfo {
     Param,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Plicity {
     Explicit,
     Implicit,
@@ -277,6 +278,261 @@ impl<'arena> Term<'arena> {
         }
     }
 
+    /// Returns `true` if the term contains an occurrence of a local variable
+    /// that was bound outside of the term.
+    pub fn has_free_locals(&self) -> bool {
+        self.binds_local_from(Index::last())
+    }
+
+    /// Returns `true` if the term contains an occurrence of the local variable,
+    /// or of a local variable that was bound before it.
+    fn binds_local_from(&self, mut var: Index) -> bool {
+        match self {
+            Term::LocalVar(_, v) => *v >= var,
+            // Conservatively assume that parameters in the metavariable's
+            // environment were bound outside of the term
+            Term::InsertedMeta(_, _, local_infos) => {
+                (local_infos.iter()).any(|local_info| matches!(local_info, LocalInfo::Param))
+            }
+            Term::ItemVar(_, _)
+            | Term::MetaVar(_, _)
+            | Term::Universe(_)
+            | Term::Prim(_, _)
+            | Term::ConstLit(_, _) => false,
+
+            Term::Ann(_, expr, r#type) => {
+                expr.binds_local_from(var) || r#type.binds_local_from(var)
+            }
+            Term::Let(_, _, def_type, def_expr, body_expr) => {
+                def_type.binds_local_from(var)
+                    || def_expr.binds_local_from(var)
+                    || body_expr.binds_local_from(var.prev())
+            }
+            Term::FunType(.., param_type, body_type) => {
+                param_type.binds_local_from(var) || body_type.binds_local_from(var.prev())
+            }
+            Term::FunLit(.., body_expr) => body_expr.binds_local_from(var.prev()),
+            Term::FunApp(.., head_expr, arg_expr) => {
+                head_expr.binds_local_from(var) || arg_expr.binds_local_from(var)
+            }
+            Term::RecordType(_, _, terms)
+            | Term::FormatRecord(_, _, terms)
+            | Term::FormatOverlap(_, _, terms) => terms.iter().any(|term| {
+                let result = term.binds_local_from(var);
+                var = var.prev();
+                result
+            }),
+            Term::RecordLit(_, _, terms) | Term::ArrayLit(_, terms) => {
+                terms.iter().any(|term| term.binds_local_from(var))
+            }
+            Term::RecordProj(_, head_expr, _) => head_expr.binds_local_from(var),
+            Term::FormatCond(_, _, format, pred) => {
+                format.binds_local_from(var) || pred.binds_local_from(var.prev())
+            }
+            Term::ConstMatch(_, scrut, branches, default_expr) => {
+                scrut.binds_local_from(var)
+                    || branches.iter().any(|(_, term)| term.binds_local_from(var))
+                    || default_expr.map_or(false, |(_, term)| term.binds_local_from(var.prev()))
+            }
+        }
+    }
+
+    /// Returns the number of occurrences of the local variable in the term.
+    pub fn local_uses(&self, mut var: Index) -> usize {
+        match self {
+            Term::LocalVar(_, v) => usize::from(*v == var),
+            Term::ItemVar(_, _)
+            | Term::MetaVar(_, _)
+            | Term::InsertedMeta(_, _, _)
+            | Term::Universe(_)
+            | Term::Prim(_, _)
+            | Term::ConstLit(_, _) => 0,
+
+            Term::Ann(_, expr, r#type) => expr.local_uses(var) + r#type.local_uses(var),
+            Term::Let(_, _, def_type, def_expr, body_expr) => {
+                def_type.local_uses(var)
+                    + def_expr.local_uses(var)
+                    + body_expr.local_uses(var.prev())
+            }
+            Term::FunType(.., param_type, body_type) => {
+                param_type.local_uses(var) + body_type.local_uses(var.prev())
+            }
+            Term::FunLit(.., body_expr) => body_expr.local_uses(var.prev()),
+            Term::FunApp(.., head_expr, arg_expr) => {
+                head_expr.local_uses(var) + arg_expr.local_uses(var)
+            }
+            Term::RecordType(_, _, terms)
+            | Term::RecordLit(_, _, terms)
+            | Term::FormatRecord(_, _, terms)
+            | Term::FormatOverlap(_, _, terms) => (terms.iter())
+                .map(|term| {
+                    let uses = term.local_uses(var);
+                    var = var.prev();
+                    uses
+                })
+                .sum(),
+            Term::RecordProj(_, head_expr, _) => head_expr.local_uses(var),
+            Term::ArrayLit(_, elem_exprs) => {
+                elem_exprs.iter().map(|term| term.local_uses(var)).sum()
+            }
+            Term::FormatCond(_, _, format, pred) => {
+                format.local_uses(var) + pred.local_uses(var.prev())
+            }
+            Term::ConstMatch(_, scrut, branches, default_expr) => {
+                scrut.local_uses(var)
+                    + branches
+                        .iter()
+                        .map(|(_, term)| term.local_uses(var))
+                        .sum::<usize>()
+                    + default_expr.map_or(0, |(_, term)| term.local_uses(var.prev()))
+            }
+        }
+    }
+
+    /// Returns `true` if the terms are syntactically equal, ignoring source
+    /// spans and the names of binders.
+    pub fn is_equal(&self, other: &Term<'_>) -> bool {
+        fn all_equal(terms0: &[Term<'_>], terms1: &[Term<'_>]) -> bool {
+            terms0.len() == terms1.len()
+                && Iterator::zip(terms0.iter(), terms1.iter()).all(|(t0, t1)| t0.is_equal(t1))
+        }
+
+        match (self, other) {
+            (Term::ItemVar(_, var0), Term::ItemVar(_, var1)) => var0 == var1,
+            (Term::LocalVar(_, var0), Term::LocalVar(_, var1)) => var0 == var1,
+            (Term::MetaVar(_, var0), Term::MetaVar(_, var1)) => var0 == var1,
+            (Term::InsertedMeta(_, var0, infos0), Term::InsertedMeta(_, var1, infos1)) => {
+                var0 == var1 && infos0 == infos1
+            }
+            (Term::Ann(_, expr0, type0), Term::Ann(_, expr1, type1)) => {
+                expr0.is_equal(expr1) && type0.is_equal(type1)
+            }
+            (Term::Let(_, _, type0, expr0, body0), Term::Let(_, _, type1, expr1, body1)) => {
+                type0.is_equal(type1) && expr0.is_equal(expr1) && body0.is_equal(body1)
+            }
+            (Term::Universe(_), Term::Universe(_)) => true,
+            (
+                Term::FunType(_, plicity0, _, param_type0, body_type0),
+                Term::FunType(_, plicity1, _, param_type1, body_type1),
+            ) => {
+                plicity0 == plicity1
+                    && param_type0.is_equal(param_type1)
+                    && body_type0.is_equal(body_type1)
+            }
+            (Term::FunLit(_, plicity0, _, body0), Term::FunLit(_, plicity1, _, body1)) => {
+                plicity0 == plicity1 && body0.is_equal(body1)
+            }
+            (Term::FunApp(_, plicity0, head0, arg0), Term::FunApp(_, plicity1, head1, arg1)) => {
+                plicity0 == plicity1 && head0.is_equal(head1) && arg0.is_equal(arg1)
+            }
+            (Term::RecordType(_, labels0, terms0), Term::RecordType(_, labels1, terms1))
+            | (Term::RecordLit(_, labels0, terms0), Term::RecordLit(_, labels1, terms1))
+            | (Term::FormatRecord(_, labels0, terms0), Term::FormatRecord(_, labels1, terms1))
+            | (Term::FormatOverlap(_, labels0, terms0), Term::FormatOverlap(_, labels1, terms1)) => {
+                labels0 == labels1 && all_equal(terms0, terms1)
+            }
+            (Term::RecordProj(_, head0, label0), Term::RecordProj(_, head1, label1)) => {
+                label0 == label1 && head0.is_equal(head1)
+            }
+            (Term::ArrayLit(_, elems0), Term::ArrayLit(_, elems1)) => all_equal(elems0, elems1),
+            (
+                Term::FormatCond(_, label0, format0, pred0),
+                Term::FormatCond(_, label1, format1, pred1),
+            ) => label0 == label1 && format0.is_equal(format1) && pred0.is_equal(pred1),
+            (Term::Prim(_, prim0), Term::Prim(_, prim1)) => prim0 == prim1,
+            (Term::ConstLit(_, const0), Term::ConstLit(_, const1)) => const0 == const1,
+            (
+                Term::ConstMatch(_, scrut0, branches0, default0),
+                Term::ConstMatch(_, scrut1, branches1, default1),
+            ) => {
+                scrut0.is_equal(scrut1)
+                    && branches0.len() == branches1.len()
+                    && Iterator::zip(branches0.iter(), branches1.iter())
+                        .all(|((c0, t0), (c1, t1))| c0 == c1 && t0.is_equal(t1))
+                    && match (default0, default1) {
+                        (Some((_, t0)), Some((_, t1))) => t0.is_equal(t1),
+                        (None, None) => true,
+                        (_, _) => false,
+                    }
+            }
+            (_, _) => false,
+        }
+    }
+
+    /// Feed the structure of the term into `state`, consistently with
+    /// [`Term::is_equal`]. Source spans and the names of binders are ignored.
+    pub fn hash_structure<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Term::ItemVar(_, var) => var.hash(state),
+            Term::LocalVar(_, var) => var.hash(state),
+            Term::MetaVar(_, var) => var.hash(state),
+            Term::InsertedMeta(_, var, local_infos) => {
+                var.hash(state);
+                local_infos.hash(state);
+            }
+            Term::Ann(_, expr, r#type) => {
+                expr.hash_structure(state);
+                r#type.hash_structure(state);
+            }
+            Term::Let(_, _, def_type, def_expr, body_expr) => {
+                def_type.hash_structure(state);
+                def_expr.hash_structure(state);
+                body_expr.hash_structure(state);
+            }
+            Term::Universe(_) => {}
+            Term::FunType(_, plicity, _, param_type, body_type) => {
+                plicity.hash(state);
+                param_type.hash_structure(state);
+                body_type.hash_structure(state);
+            }
+            Term::FunLit(_, plicity, _, body_expr) => {
+                plicity.hash(state);
+                body_expr.hash_structure(state);
+            }
+            Term::FunApp(_, plicity, head_expr, arg_expr) => {
+                plicity.hash(state);
+                head_expr.hash_structure(state);
+                arg_expr.hash_structure(state);
+            }
+            Term::RecordType(_, labels, terms)
+            | Term::RecordLit(_, labels, terms)
+            | Term::FormatRecord(_, labels, terms)
+            | Term::FormatOverlap(_, labels, terms) => {
+                labels.hash(state);
+                terms.iter().for_each(|term| term.hash_structure(state));
+            }
+            Term::RecordProj(_, head_expr, label) => {
+                head_expr.hash_structure(state);
+                label.hash(state);
+            }
+            Term::ArrayLit(_, elem_exprs) => {
+                elem_exprs.len().hash(state);
+                elem_exprs
+                    .iter()
+                    .for_each(|term| term.hash_structure(state));
+            }
+            Term::FormatCond(_, label, format, pred) => {
+                label.hash(state);
+                format.hash_structure(state);
+                pred.hash_structure(state);
+            }
+            Term::Prim(_, prim) => prim.hash(state),
+            Term::ConstLit(_, r#const) => r#const.hash(state),
+            Term::ConstMatch(_, head_expr, branches, default_expr) => {
+                head_expr.hash_structure(state);
+                branches.len().hash(state);
+                for (r#const, term) in branches.iter() {
+                    r#const.hash(state);
+                    term.hash_structure(state);
+                }
+                if let Some((_, term)) = default_expr {
+                    term.hash_structure(state);
+                }
+            }
+        }
+    }
+
     pub fn is_error(&self) -> bool {
         matches!(self, Term::Prim(_, Prim::ReportedError))
     }
@@ -285,7 +541,7 @@ impl<'arena> Term<'arena> {
 macro_rules! def_prims {
     ($($(#[$prim_attr:meta])* $PrimName:ident => $prim_name:literal),* $(,)?) => {
         /// Primitives.
-        #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
         pub enum Prim {
             $($(#[$prim_attr])* $PrimName),*
         }
@@ -384,6 +640,20 @@ def_prims! {
     FormatF64Be => "f64be",
     /// 64-bit, IEEE-754 floating point formats (little-endian).
     FormatF64Le => "f64le",
+    /// Signed 16.16 fixed-point number formats (big-endian).
+    FormatFixed16Dot16Be => "fixed16_16be",
+    /// Signed 16.16 fixed-point number formats (little-endian).
+    FormatFixed16Dot16Le => "fixed16_16le",
+    /// Signed 2.14 fixed-point number formats (big-endian).
+    FormatFixed2Dot14Be => "fixed2_14be",
+    /// Signed 2.14 fixed-point number formats (little-endian).
+    FormatFixed2Dot14Le => "fixed2_14le",
+    /// Unsigned LEB128 variable-length integer formats.
+    FormatULeb128 => "uleb128",
+    /// Signed LEB128 variable-length integer formats.
+    FormatSLeb128 => "sleb128",
+    /// Parse a format, discarding its result, and then parse another format.
+    FormatThen => "format_then",
     /// Repeat formats up to an unsigned 8-bit length.
     FormatRepeatLen8 => "repeat_len8",
     /// Repeat formats up to an unsigned 16-bit length.
@@ -392,8 +662,24 @@ def_prims! {
     FormatRepeatLen32 => "repeat_len32",
     /// Repeat formats up to an unsigned 64-bit length.
     FormatRepeatLen64 => "repeat_len64",
+    /// Repeat formats up to an unsigned 8-bit length prefix.
+    FormatRepeatPrefixedU8 => "repeat_prefixed_u8",
+    /// Repeat formats up to an unsigned 16-bit, big-endian length prefix.
+    FormatRepeatPrefixedU16Be => "repeat_prefixed_u16be",
+    /// Repeat formats up to an unsigned 16-bit, little-endian length prefix.
+    FormatRepeatPrefixedU16Le => "repeat_prefixed_u16le",
+    /// Repeat formats up to an unsigned 32-bit, big-endian length prefix.
+    FormatRepeatPrefixedU32Be => "repeat_prefixed_u32be",
+    /// Repeat formats up to an unsigned 32-bit, little-endian length prefix.
+    FormatRepeatPrefixedU32Le => "repeat_prefixed_u32le",
+    /// Repeat formats up to an unsigned 64-bit, big-endian length prefix.
+    FormatRepeatPrefixedU64Be => "repeat_prefixed_u64be",
+    /// Repeat formats up to an unsigned 64-bit, little-endian length prefix.
+    FormatRepeatPrefixedU64Le => "repeat_prefixed_u64le",
     /// Repeat a format until the length of the given parse scope is reached.
     FormatRepeatUntilEnd => "repeat_until_end",
+    /// Repeat a format until an element matching a predicate is reached.
+    FormatRepeatUntil => "repeat_until",
     /// Limit the format to an unsigned 8-bit byte length.
     FormatLimit8 => "limit8",
     /// Limit the format to an unsigned 16-bit byte length.
@@ -404,6 +690,9 @@ def_prims! {
     FormatLimit64 => "limit64",
     /// A format which returns the current position in the input stream.
     FormatStreamPos => "stream_pos",
+    /// A format that skips padding up to the next multiple of the given
+    /// alignment in the input stream.
+    FormatAlign => "align",
     /// A format that links to another location in the binary data stream,
     /// relative to a base position.
     FormatLink => "link",
@@ -413,6 +702,8 @@ def_prims! {
     FormatSucceed => "succeed",
     /// A format that always fails to parse.
     FormatFail => "fail",
+    /// A format that fails to parse if any data remains in the current buffer.
+    FormatAssertEof => "assert_eof",
     /// Unwrap an option, or fail to parse.
     FormatUnwrap => "unwrap",
     /// Format representations.
@@ -600,6 +891,13 @@ pub enum Const {
     Ref(usize),
 }
 
+/// Constants are compared structurally, ignoring [`UIntStyle`]s.
+///
+/// Floating point constants are compared by their bit patterns, not by IEEE 754
+/// equality. This means that `+0.0` and `-0.0` are distinct, that `NaN` is
+/// equal to itself, and that `NaN`s with different payloads are distinct. This
+/// keeps the equality reflexive, which is relied upon during conversion
+/// checking, and preserves the sign bit for formats where it is significant.
 impl PartialEq for Const {
     fn eq(&self, other: &Const) -> bool {
         match (*self, *other) {
@@ -623,6 +921,30 @@ impl PartialEq for Const {
 
 impl Eq for Const {}
 
+/// Constants are hashed consistently with their [`PartialEq`] implementation,
+/// ignoring [`UIntStyle`]s and hashing floating point constants by their bit
+/// patterns.
+impl Hash for Const {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match *self {
+            Const::Bool(a) => a.hash(state),
+            Const::U8(a, _) => a.hash(state),
+            Const::U16(a, _) => a.hash(state),
+            Const::U32(a, _) => a.hash(state),
+            Const::U64(a, _) => a.hash(state),
+            Const::S8(a) => a.hash(state),
+            Const::S16(a) => a.hash(state),
+            Const::S32(a) => a.hash(state),
+            Const::S64(a) => a.hash(state),
+            Const::F32(a) => a.to_bits().hash(state),
+            Const::F64(a) => a.to_bits().hash(state),
+            Const::Pos(a) => a.hash(state),
+            Const::Ref(a) => a.hash(state),
+        }
+    }
+}
+
 impl PartialOrd for Const {
     fn partial_cmp(&self, other: &Const) -> Option<std::cmp::Ordering> {
         Some(self.cmp(other))
@@ -746,4 +1068,27 @@ mod tests {
     fn term_size() {
         assert_eq!(std::mem::size_of::<Term>(), 56);
     }
+
+    #[test]
+    fn const_float_eq() {
+        assert_eq!(Const::F32(0.0), Const::F32(0.0));
+        assert_ne!(Const::F32(0.0), Const::F32(-0.0));
+        assert_eq!(Const::F32(f32::NAN), Const::F32(f32::NAN));
+        assert_ne!(
+            Const::F32(f32::NAN),
+            Const::F32(f32::from_bits(0x7fc0_0001))
+        );
+        assert_eq!(
+            Const::F32(f32::MIN_POSITIVE / 2.0),
+            Const::F32(f32::from_bits(0x0040_0000))
+        );
+
+        assert_eq!(Const::F64(0.0), Const::F64(0.0));
+        assert_ne!(Const::F64(0.0), Const::F64(-0.0));
+        assert_eq!(Const::F64(f64::NAN), Const::F64(f64::NAN));
+        assert_ne!(
+            Const::F64(f64::NAN),
+            Const::F64(f64::from_bits(0x7ff8_0000_0000_0001))
+        );
+    }
 }
@@ -8,9 +8,10 @@ use std::slice::SliceIndex;
 use std::sync::Arc;
 
 use crate::core::semantics::{self, ArcValue, Elim, Head, Value};
-use crate::core::{Const, Item, Module, Prim, Term, UIntStyle};
-use crate::env::{EnvLen, SharedEnv, UniqueEnv};
+use crate::core::{Const, Item, Module, Plicity, Prim, Term, UIntStyle};
+use crate::env::{self, EnvLen, SharedEnv, UniqueEnv};
 use crate::source::{Span, Spanned};
+use crate::symbol::Symbol;
 
 #[derive(Clone, Debug)]
 pub enum ReadError<'arena> {
@@ -19,10 +20,58 @@ pub enum ReadError<'arena> {
     UnknownItem,
     UnwrappedNone(Span),
     ReadFailFormat(Span),
+    TrailingData(Span, usize),
+    DepthExceeded(Span),
+    NoProgress(Span),
     CondFailure(Span, ArcValue<'arena>),
     BufferError(Span, BufferError),
 }
 
+impl<'arena> ReadError<'arena> {
+    /// Classify the error, for callers that need to decide how to recover.
+    pub fn kind(&self) -> ReadErrorKind {
+        match self {
+            ReadError::InvalidFormat(_) | ReadError::InvalidValue(_) => ReadErrorKind::Internal,
+            ReadError::UnknownItem => ReadErrorKind::UnknownItem,
+            ReadError::UnwrappedNone(_) => ReadErrorKind::UnwrappedNone,
+            ReadError::ReadFailFormat(_) => ReadErrorKind::Fail,
+            ReadError::TrailingData(_, _) => ReadErrorKind::TrailingData,
+            ReadError::DepthExceeded(_) => ReadErrorKind::DepthExceeded,
+            ReadError::NoProgress(_) => ReadErrorKind::NoProgress,
+            ReadError::CondFailure(_, _) => ReadErrorKind::Predicate,
+            ReadError::BufferError(_, err) => err.kind(),
+        }
+    }
+}
+
+/// A classification of [`ReadError`]s.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ReadErrorKind {
+    /// The end of the buffer was reached before all data could be read.
+    Eof,
+    /// An offset was outside the bounds of the buffer.
+    InvalidOffset,
+    /// A position could not be represented.
+    Overflow,
+    /// The predicate on a conditional format did not succeed.
+    Predicate,
+    /// A fail format was read.
+    Fail,
+    /// Data remained in the buffer when the end was asserted.
+    TrailingData,
+    /// Formats were nested more deeply than the maximum read depth.
+    DepthExceeded,
+    /// A repeated format read an element without consuming any data.
+    NoProgress,
+    /// An unwrap format was applied to a none value.
+    UnwrappedNone,
+    /// The item to be read was not defined in the module.
+    UnknownItem,
+    /// An ill-formed format or value was encountered, which should have been
+    /// prevented by elaboration.
+    Internal,
+}
+
 impl<'arena> fmt::Display for ReadError<'arena> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -31,6 +80,9 @@ impl<'arena> fmt::Display for ReadError<'arena> {
             ReadError::UnwrappedNone(_) => f.write_str("unwrapped none"),
             ReadError::UnknownItem => f.write_str("unknown item"),
             ReadError::ReadFailFormat(_) => f.write_str("read a fail format"),
+            ReadError::TrailingData(_, _) => f.write_str("trailing data"),
+            ReadError::DepthExceeded(_) => f.write_str("maximum read depth exceeded"),
+            ReadError::NoProgress(_) => f.write_str("repeated format made no progress"),
             ReadError::CondFailure(_, _) => f.write_str("conditional format failed"),
             ReadError::BufferError(_, err) => fmt::Display::fmt(&err, f),
         }
@@ -100,6 +152,21 @@ impl<'data> Buffer<'data> {
         })
     }
 
+    /// Return a buffer of `len` bytes, starting at an offset measured from the
+    /// start of the base buffer. Relative offsets into the returned buffer are
+    /// measured from the start of the sub-buffer, while offsets remain
+    /// measured from the start of the base buffer.
+    pub fn sub_buffer(&self, offset: usize, len: usize) -> Result<Buffer<'data>, BufferError> {
+        let relative_offset = usize::checked_sub(offset, self.start_offset)
+            .ok_or(BufferError::SetOffsetBeforeStartOfBuffer { offset })?;
+        let end = usize::checked_add(relative_offset, len).ok_or(BufferError::PositionOverflow)?;
+
+        Ok(Buffer::new(
+            offset,
+            self.get_relative(relative_offset..end)?,
+        ))
+    }
+
     /// Get a slice of the bytes in the buffer, relative to the start of the
     /// buffer.
     fn get_relative<I: SliceIndex<[u8]>>(&self, index: I) -> Result<&'data I::Output, BufferError> {
@@ -171,6 +238,18 @@ impl<'data> BufferReader<'data> {
         ))
     }
 
+    /// Create an independent reader over `len` bytes, starting at an offset
+    /// measured from the start of the base buffer. Relative offsets in the
+    /// returned reader are measured from the start of the sub-buffer, and this
+    /// reader is left unchanged.
+    pub fn sub_reader(
+        &self,
+        offset: usize,
+        len: usize,
+    ) -> Result<BufferReader<'data>, BufferError> {
+        Ok(self.buffer.sub_buffer(offset, len)?.reader())
+    }
+
     /// Set the offset of the reader relative to the start of the backing
     /// buffer.
     pub fn set_relative_offset(&mut self, relative_offset: usize) -> Result<(), BufferError> {
@@ -228,9 +307,20 @@ pub enum BufferError {
     SetOffsetAfterEndOfBuffer { offset: Option<usize> },
     UnexpectedEndOfBuffer,
     PositionOverflow,
+    VarIntOverflow,
 }
 
 impl BufferError {
+    /// Classify the error, for callers that need to decide how to recover.
+    pub fn kind(&self) -> ReadErrorKind {
+        match self {
+            BufferError::SetOffsetBeforeStartOfBuffer { .. }
+            | BufferError::SetOffsetAfterEndOfBuffer { .. } => ReadErrorKind::InvalidOffset,
+            BufferError::UnexpectedEndOfBuffer => ReadErrorKind::Eof,
+            BufferError::PositionOverflow | BufferError::VarIntOverflow => ReadErrorKind::Overflow,
+        }
+    }
+
     fn with_span<'arena>(self, span: Span) -> ReadError<'arena> {
         ReadError::BufferError(span, self)
     }
@@ -247,18 +337,26 @@ impl fmt::Display for BufferError {
             }
             BufferError::UnexpectedEndOfBuffer => f.write_str("unexpected end of buffer"),
             BufferError::PositionOverflow => f.write_str("position overflow"),
+            BufferError::VarIntOverflow => f.write_str("variable-length integer overflow"),
         }
     }
 }
 
 impl std::error::Error for BufferError {}
 
+/// The default maximum depth that formats can be nested to when reading.
+pub const DEFAULT_MAX_DEPTH: usize = 1024;
+
 pub struct Context<'arena, 'data> {
     item_exprs: UniqueEnv<ArcValue<'arena>>,
     local_exprs: SharedEnv<ArcValue<'arena>>,
     initial_buffer: Buffer<'data>,
     pending_formats: Vec<(usize, ArcValue<'arena>)>,
     cached_refs: HashMap<usize, Vec<ParsedRef<'arena>>>,
+    /// The current depth of nested formats being read.
+    depth: usize,
+    /// The maximum depth that formats can be nested to when reading.
+    max_depth: usize,
 }
 
 pub struct ParsedRef<'arena> {
@@ -278,9 +376,17 @@ impl<'arena, 'data> Context<'arena, 'data> {
             initial_buffer,
             pending_formats: Vec::new(),
             cached_refs: HashMap::new(),
+            depth: 0,
+            max_depth: DEFAULT_MAX_DEPTH,
         }
     }
 
+    /// Set the maximum depth that formats can be nested to when reading. This
+    /// can be lowered to limit the resources used when reading untrusted data.
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
     fn eval_env(&mut self) -> semantics::EvalEnv<'arena, '_> {
         let elim_env = semantics::ElimEnv::new(&self.item_exprs, [][..].into());
         semantics::EvalEnv::new(elim_env, &mut self.local_exprs)
@@ -322,6 +428,22 @@ impl<'arena, 'data> Context<'arena, 'data> {
         &mut self,
         reader: &mut BufferReader<'data>,
         format: &ArcValue<'arena>,
+    ) -> Result<ArcValue<'arena>, ReadError<'arena>> {
+        if self.depth >= self.max_depth {
+            return Err(ReadError::DepthExceeded(format.span()));
+        }
+
+        self.depth += 1;
+        let result = self.read_format_impl(reader, format);
+        self.depth -= 1;
+
+        result
+    }
+
+    fn read_format_impl(
+        &mut self,
+        reader: &mut BufferReader<'data>,
+        format: &ArcValue<'arena>,
     ) -> Result<ArcValue<'arena>, ReadError<'arena>> {
         match format.as_ref() {
             Value::Stuck(Head::Prim(prim), slice) => {
@@ -425,11 +547,26 @@ impl<'arena, 'data> Context<'arena, 'data> {
             (Prim::FormatF32Le, []) => read_const(reader, span, read_f32le, Const::F32),
             (Prim::FormatF64Be, []) => read_const(reader, span, read_f64be, Const::F64),
             (Prim::FormatF64Le, []) => read_const(reader, span, read_f64le, Const::F64),
+            (Prim::FormatFixed16Dot16Be, []) => read_const(reader, span, read_s32be, |num| Const::F64(fixed_to_f64(num, 16))),
+            (Prim::FormatFixed16Dot16Le, []) => read_const(reader, span, read_s32le, |num| Const::F64(fixed_to_f64(num, 16))),
+            (Prim::FormatFixed2Dot14Be, []) => read_const(reader, span, read_s16be, |num| Const::F64(fixed_to_f64(num.into(), 14))),
+            (Prim::FormatFixed2Dot14Le, []) => read_const(reader, span, read_s16le, |num| Const::F64(fixed_to_f64(num.into(), 14))),
+            (Prim::FormatULeb128, []) => read_const(reader, span, read_uleb128, |num| Const::U64(num, UIntStyle::Decimal)),
+            (Prim::FormatSLeb128, []) => read_const(reader, span, read_sleb128, Const::S64),
+            (Prim::FormatThen, [FunApp(_, format0), FunApp(_, format1)]) => self.read_then(reader, format0, format1),
             (Prim::FormatRepeatLen8, [FunApp(_, len), FunApp(_, format)]) => self.read_repeat_len(reader, span, len, format),
             (Prim::FormatRepeatLen16, [FunApp(_, len), FunApp(_, format)]) => self.read_repeat_len(reader, span, len, format),
             (Prim::FormatRepeatLen32, [FunApp(_, len), FunApp(_, format)]) => self.read_repeat_len(reader, span, len, format),
             (Prim::FormatRepeatLen64, [FunApp(_, len), FunApp(_, format)]) => self.read_repeat_len(reader, span, len, format),
+            (Prim::FormatRepeatPrefixedU8, [FunApp(_, format)]) => self.read_repeat_prefixed(reader, span, read_u8, format),
+            (Prim::FormatRepeatPrefixedU16Be, [FunApp(_, format)]) => self.read_repeat_prefixed(reader, span, read_u16be, format),
+            (Prim::FormatRepeatPrefixedU16Le, [FunApp(_, format)]) => self.read_repeat_prefixed(reader, span, read_u16le, format),
+            (Prim::FormatRepeatPrefixedU32Be, [FunApp(_, format)]) => self.read_repeat_prefixed(reader, span, read_u32be, format),
+            (Prim::FormatRepeatPrefixedU32Le, [FunApp(_, format)]) => self.read_repeat_prefixed(reader, span, read_u32le, format),
+            (Prim::FormatRepeatPrefixedU64Be, [FunApp(_, format)]) => self.read_repeat_prefixed(reader, span, read_u64be, format),
+            (Prim::FormatRepeatPrefixedU64Le, [FunApp(_, format)]) => self.read_repeat_prefixed(reader, span, read_u64le, format),
             (Prim::FormatRepeatUntilEnd, [FunApp(_,format)]) => self.read_repeat_until_end(reader, format),
+            (Prim::FormatRepeatUntil, [FunApp(_, format), FunApp(_, pred)]) => self.read_repeat_until(reader, span, format, pred),
             (Prim::FormatLimit8, [FunApp(_, limit), FunApp(_, format)]) => self.read_limit(reader, limit, format),
             (Prim::FormatLimit16, [FunApp(_, limit), FunApp(_, format)]) => self.read_limit(reader, limit, format),
             (Prim::FormatLimit32, [FunApp(_, limit), FunApp(_, format)]) => self.read_limit(reader, limit, format),
@@ -437,8 +574,10 @@ impl<'arena, 'data> Context<'arena, 'data> {
             (Prim::FormatLink, [FunApp(_, pos), FunApp(_, format)]) => self.read_link(span, pos, format),
             (Prim::FormatDeref, [FunApp(_, format), FunApp(_, r#ref)]) => self.read_deref(format, r#ref),
             (Prim::FormatStreamPos, []) => read_stream_pos(reader, span),
+            (Prim::FormatAlign, [FunApp(_, alignment)]) => read_align(reader, span, alignment),
             (Prim::FormatSucceed, [_, FunApp(_, elem)]) => Ok(elem.clone()),
             (Prim::FormatFail, []) => Err(ReadError::ReadFailFormat(span)),
+            (Prim::FormatAssertEof, []) => read_assert_eof(reader, span),
             (Prim::FormatUnwrap, [_, FunApp(_, option)]) => match option.match_prim_spine() {
                 Some((Prim::OptionSome, [_, FunApp(_, elem)])) => Ok(elem.clone()),
                 Some((Prim::OptionNone, [_])) => Err(ReadError::UnwrappedNone(span)),
@@ -448,6 +587,16 @@ impl<'arena, 'data> Context<'arena, 'data> {
         }
     }
 
+    fn read_then(
+        &mut self,
+        reader: &mut BufferReader<'data>,
+        format0: &ArcValue<'arena>,
+        format1: &ArcValue<'arena>,
+    ) -> Result<ArcValue<'arena>, ReadError<'arena>> {
+        self.read_format(reader, format0)?;
+        self.read_format(reader, format1)
+    }
+
     fn read_repeat_len(
         &mut self,
         reader: &mut BufferReader<'data>,
@@ -470,6 +619,22 @@ impl<'arena, 'data> Context<'arena, 'data> {
         Ok(Spanned::new(span, Arc::new(Value::ArrayLit(elem_exprs))))
     }
 
+    fn read_repeat_prefixed<T: Into<u64>>(
+        &mut self,
+        reader: &mut BufferReader<'data>,
+        span: Span,
+        read_len: fn(&mut BufferReader<'data>) -> Result<T, BufferError>,
+        elem_format: &ArcValue<'arena>,
+    ) -> Result<ArcValue<'arena>, ReadError<'arena>> {
+        let len = read_len(reader).map_err(|err| err.with_span(span))?.into();
+
+        let elem_exprs = (0..len)
+            .map(|_| self.read_format(reader, elem_format))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Spanned::new(span, Arc::new(Value::ArrayLit(elem_exprs))))
+    }
+
     fn read_repeat_until_end(
         &mut self,
         reader: &mut BufferReader<'data>,
@@ -481,6 +646,10 @@ impl<'arena, 'data> Context<'arena, 'data> {
         loop {
             match self.read_format(reader, elem_format) {
                 Ok(elem) => {
+                    // Avoid looping forever on elements that consume no data
+                    if reader.relative_offset() == current_offset {
+                        return Err(ReadError::NoProgress(elem_format.span()));
+                    }
                     elems.push(elem);
                     current_offset = reader.relative_offset();
                 }
@@ -498,6 +667,37 @@ impl<'arena, 'data> Context<'arena, 'data> {
         }
     }
 
+    fn read_repeat_until(
+        &mut self,
+        reader: &mut BufferReader<'data>,
+        span: Span,
+        elem_format: &ArcValue<'arena>,
+        pred: &ArcValue<'arena>,
+    ) -> Result<ArcValue<'arena>, ReadError<'arena>> {
+        let mut elems = Vec::new();
+
+        loop {
+            let elem_offset = reader.relative_offset();
+            let elem = self.read_format(reader, elem_format)?;
+            let pred_res = self
+                .elim_env()
+                .fun_app(Plicity::Explicit, pred.clone(), elem.clone());
+
+            match pred_res.as_ref() {
+                // The terminating element is consumed, but not included in the array
+                Value::ConstLit(Const::Bool(true)) => break,
+                Value::ConstLit(Const::Bool(false)) if reader.relative_offset() == elem_offset => {
+                    // Avoid looping forever on elements that consume no data
+                    return Err(ReadError::NoProgress(elem_format.span()));
+                }
+                Value::ConstLit(Const::Bool(false)) => elems.push(elem),
+                _ => return Err(ReadError::InvalidValue(pred.span())),
+            }
+        }
+
+        Ok(Spanned::new(span, Arc::new(Value::ArrayLit(elems))))
+    }
+
     fn read_limit(
         &mut self,
         reader: &BufferReader<'data>,
@@ -606,6 +806,89 @@ impl<'arena, 'data> Context<'arena, 'data> {
     }
 }
 
+/// Read the data using the item named `item` in `module` as the entrypoint
+/// format.
+///
+/// This is intended to be used as a fuzzing target: malformed data should
+/// always result in a [`ReadError`], rather than a panic or an out of bounds
+/// read.
+pub fn read_fuzz<'arena>(
+    module: &Module<'arena>,
+    item: Symbol,
+    data: &[u8],
+) -> Result<HashMap<usize, Vec<ParsedRef<'arena>>>, ReadError<'arena>> {
+    let level = Iterator::zip(env::levels(), module.items.iter())
+        .find_map(|(level, Item::Def { label, .. })| (*label == item).then_some(level))
+        .ok_or(ReadError::UnknownItem)?;
+
+    let mut context = Context::new(Buffer::from(data));
+    context.add_module(module);
+    context.read_entrypoint(&Term::ItemVar(Span::Empty, level))
+}
+
+/// Generate a deterministic corpus of random data for [`read_fuzz`], where
+/// each input is shorter than `max_len` bytes.
+///
+/// The same `seed` always produces the same inputs, allowing failures to be
+/// reproduced.
+pub fn fuzz_corpus(seed: u64, max_len: usize) -> impl Iterator<Item = Vec<u8>> {
+    // Xorshift generators get stuck at zero
+    let mut state = seed.max(1);
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    std::iter::repeat_with(move || {
+        let len = next() as usize % max_len.max(1);
+        (0..len).map(|_| next() as u8).collect()
+    })
+}
+
+fn read_assert_eof<'arena>(
+    reader: &mut BufferReader<'_>,
+    span: Span,
+) -> Result<ArcValue<'arena>, ReadError<'arena>> {
+    match reader.remaining_len() {
+        0 => Ok(Spanned::new(
+            span,
+            Arc::new(Value::RecordLit(&[], Vec::new())),
+        )),
+        remaining => Err(ReadError::TrailingData(span, remaining)),
+    }
+}
+
+fn read_align<'arena>(
+    reader: &mut BufferReader<'_>,
+    span: Span,
+    alignment: &ArcValue<'arena>,
+) -> Result<ArcValue<'arena>, ReadError<'arena>> {
+    let alignment = match alignment.as_ref() {
+        Value::ConstLit(Const::U64(alignment, _)) => usize::try_from(*alignment)
+            .map_err(|_| BufferError::PositionOverflow.with_span(span))?,
+        _ => return Err(ReadError::InvalidValue(alignment.span())),
+    };
+
+    // An alignment of zero is treated the same as an alignment of one
+    let offset = reader.offset().map_err(|err| err.with_span(span))?;
+    let padding = match alignment {
+        0 => 0,
+        alignment => (alignment - offset % alignment) % alignment,
+    };
+    let aligned_offset = (offset.checked_add(padding))
+        .ok_or_else(|| BufferError::PositionOverflow.with_span(span))?;
+    reader
+        .set_offset(aligned_offset)
+        .map_err(|err| err.with_span(span))?;
+
+    Ok(Spanned::new(
+        span,
+        Arc::new(Value::RecordLit(&[], Vec::new())),
+    ))
+}
+
 fn read_stream_pos<'arena>(
     reader: &mut BufferReader<'_>,
     span: Span,
@@ -666,3 +949,349 @@ read_multibyte_prim!(read_f32le, from_le_bytes, f32);
 read_multibyte_prim!(read_f32be, from_be_bytes, f32);
 read_multibyte_prim!(read_f64le, from_le_bytes, f64);
 read_multibyte_prim!(read_f64be, from_be_bytes, f64);
+
+/// Read an unsigned LEB128 integer, stopping at the first byte with the high
+/// bit clear.
+fn read_uleb128(reader: &mut BufferReader<'_>) -> Result<u64, BufferError> {
+    let mut num = 0;
+    let mut shift = 0;
+    loop {
+        let byte = reader.read_byte()?;
+        let bits = u64::from(byte & 0x7f);
+        if shift > 63 || (shift == 63 && bits > 0x01) {
+            return Err(BufferError::VarIntOverflow);
+        }
+        num |= bits << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            return Ok(num);
+        }
+    }
+}
+
+/// Read a signed LEB128 integer, stopping at the first byte with the high bit
+/// clear.
+fn read_sleb128(reader: &mut BufferReader<'_>) -> Result<i64, BufferError> {
+    let mut num = 0;
+    let mut shift = 0;
+    loop {
+        let byte = reader.read_byte()?;
+        let bits = i64::from(byte & 0x7f);
+        if shift > 63 || (shift == 63 && bits != 0x00 && bits != 0x7f) {
+            return Err(BufferError::VarIntOverflow);
+        }
+        num |= bits << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            // Sign extend from the last byte that was read
+            if shift < 64 && byte & 0x40 != 0 {
+                num |= -1 << shift;
+            }
+            return Ok(num);
+        }
+    }
+}
+
+/// Convert a signed fixed-point number with `frac_bits` fractional bits to a
+/// floating point number.
+fn fixed_to_f64(num: i32, frac_bits: i32) -> f64 {
+    f64::from(num) / 2.0_f64.powi(frac_bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use scoped_arena::Scope;
+
+    use super::*;
+    use crate::env::Level;
+    use crate::files::FileId;
+    use crate::surface::elaboration::{self, ItemEnv};
+
+    const MODULE_SOURCE: &str = r#"
+        def chunk = {
+            len <- u8,
+            data <- repeat_len8 len u16be,
+            flags <- u8 where u8_lte flags 3,
+        };
+
+        def main = {
+            start <- stream_pos,
+            magic <- u16be,
+            offset <- u32be,
+            link <- link (pos_add_u32 start offset) chunk,
+            first <- deref link,
+            count <- u16le,
+            chunks <- repeat_len16 count chunk,
+            padding <- align 4,
+            end <- assert_eof,
+        };
+    "#;
+
+    fn elab_module<'arena>(scope: &'arena Scope<'arena>, source: &str) -> Module<'arena> {
+        let source = String::from(source).try_into().unwrap();
+        let (surface_module, messages) = crate::surface::Module::parse(scope, &source);
+        assert!(messages.is_empty());
+
+        let file_id = FileId::try_from(1).unwrap();
+        let mut elab_context = elaboration::Context::new(file_id, scope, ItemEnv::new());
+        elab_context.elab_module(scope, &surface_module, &mut |message| {
+            panic!("unexpected message: {message:?}")
+        })
+    }
+
+    #[test]
+    fn read_fuzz_random_data() {
+        let scope = Scope::new();
+        let module = elab_module(&scope, MODULE_SOURCE);
+        let main = Symbol::intern("main");
+
+        for data in fuzz_corpus(0x2545_f491_4f6c_dd1d, 64).take(1000) {
+            // Any result is fine, so long as we don't panic
+            let _ = read_fuzz(&module, main, &data);
+        }
+    }
+
+    #[test]
+    fn fuzz_corpus_seeds() {
+        let corpus = |seed| fuzz_corpus(seed, 16).take(8).collect::<Vec<_>>();
+
+        assert_eq!(corpus(1), corpus(1));
+        assert_ne!(corpus(1), corpus(2));
+        assert!(corpus(3).iter().all(|data| data.len() < 16));
+    }
+
+    #[test]
+    fn read_fuzz_unknown_item() {
+        let module = Module { items: &[] };
+        let result = read_fuzz(&module, Symbol::intern("main"), &[]);
+        assert!(matches!(result, Err(ReadError::UnknownItem)));
+    }
+
+    #[test]
+    fn max_depth() {
+        let scope = Scope::new();
+        let module = elab_module(&scope, "def main = { a <- { b <- { c <- u8 } } };");
+        let main = Term::ItemVar(Span::Empty, Level::first());
+
+        let read_with_max_depth = |max_depth| {
+            let mut context = Context::new(Buffer::from(&[42][..]));
+            context.add_module(&module);
+            context.set_max_depth(max_depth);
+            context.read_entrypoint(&main)
+        };
+
+        assert!(read_with_max_depth(4).is_ok());
+        assert!(matches!(
+            read_with_max_depth(3),
+            Err(ReadError::DepthExceeded(_)),
+        ));
+    }
+
+    #[test]
+    fn repeat_no_progress() {
+        let scope = Scope::new();
+        let read = |source| {
+            let module = elab_module(&scope, source);
+            let main = Term::ItemVar(Span::Empty, Level::first());
+
+            let mut context = Context::new(Buffer::from(&[1, 2, 3][..]));
+            context.add_module(&module);
+            context.read_entrypoint(&main)
+        };
+
+        assert!(matches!(
+            read("def main = repeat_until_end (succeed (0 : U8));"),
+            Err(ReadError::NoProgress(_)),
+        ));
+        assert!(matches!(
+            read("def main = repeat_until (succeed (0 : U8)) (fun _ => false);"),
+            Err(ReadError::NoProgress(_)),
+        ));
+        // Zero-width terminators are still allowed
+        assert!(read("def main = repeat_until (succeed (0 : U8)) (fun _ => true);").is_ok());
+        assert!(read("def main = repeat_until_end u8;").is_ok());
+    }
+
+    #[test]
+    fn fixed_point_formats() {
+        let scope = Scope::new();
+        let read_f64 = |source, data: &[u8]| {
+            let module = elab_module(&scope, source);
+            let main = Term::ItemVar(Span::Empty, Level::first());
+
+            let mut context = Context::new(Buffer::from(data));
+            context.add_module(&module);
+            let refs = context.read_entrypoint(&main).unwrap();
+            match refs[&0][..] {
+                [ParsedRef { ref expr, .. }] => match expr.as_ref() {
+                    Value::ConstLit(Const::F64(num)) => *num,
+                    _ => panic!("expected an `F64` constant"),
+                },
+                _ => panic!("expected a single parsed reference"),
+            }
+        };
+
+        let data = [0x00, 0x01, 0x80, 0x00];
+        assert_eq!(read_f64("def main = fixed16_16be;", &data), 1.5);
+        assert_eq!(read_f64("def main = fixed16_16le;", &data), 128.00390625);
+        assert_eq!(read_f64("def main = fixed2_14be;", &data[2..]), -2.0);
+        assert_eq!(read_f64("def main = fixed2_14le;", &[0x00, 0x60]), 1.5);
+    }
+
+    #[test]
+    fn leb128_formats() {
+        let scope = Scope::new();
+        let read_leb128 = |source, data: &[u8]| {
+            let module = elab_module(&scope, source);
+            let main = Term::ItemVar(Span::Empty, Level::first());
+
+            let mut context = Context::new(Buffer::from(data));
+            context.add_module(&module);
+            let refs = context.read_entrypoint(&main)?;
+            match refs[&0][..] {
+                [ParsedRef { ref expr, .. }] => match expr.as_ref() {
+                    Value::ConstLit(Const::U64(num, _)) => Ok(i128::from(*num)),
+                    Value::ConstLit(Const::S64(num)) => Ok(i128::from(*num)),
+                    _ => panic!("expected a `U64` or `S64` constant"),
+                },
+                _ => panic!("expected a single parsed reference"),
+            }
+        };
+
+        assert_eq!(
+            read_leb128("def main = uleb128;", &[0x96, 0x01]).unwrap(),
+            150
+        );
+        assert_eq!(read_leb128("def main = uleb128;", &[0x7f]).unwrap(), 127);
+        assert_eq!(
+            read_leb128("def main = sleb128;", &[0x96, 0x01]).unwrap(),
+            150
+        );
+        assert_eq!(read_leb128("def main = sleb128;", &[0x7f]).unwrap(), -1);
+        assert_eq!(
+            read_leb128("def main = sleb128;", &[0x80, 0x7f]).unwrap(),
+            -128
+        );
+
+        let max_u64 = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01];
+        let min_s64 = [0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x7f];
+        assert_eq!(
+            read_leb128("def main = uleb128;", &max_u64).unwrap(),
+            u64::MAX.into()
+        );
+        assert_eq!(
+            read_leb128("def main = sleb128;", &min_s64).unwrap(),
+            i64::MIN.into()
+        );
+
+        // Encodings that exceed the width of the host integer
+        let too_wide = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x03];
+        let too_long = [
+            0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00,
+        ];
+        for source in ["def main = uleb128;", "def main = sleb128;"] {
+            for data in [&too_wide[..], &too_long[..]] {
+                assert!(matches!(
+                    read_leb128(source, data),
+                    Err(ReadError::BufferError(_, BufferError::VarIntOverflow)),
+                ));
+            }
+            assert!(matches!(
+                read_leb128(source, &[0x96]),
+                Err(ReadError::BufferError(
+                    _,
+                    BufferError::UnexpectedEndOfBuffer
+                )),
+            ));
+        }
+    }
+
+    #[test]
+    fn sub_reader() {
+        // A header, followed by a table containing an offset to a nested table,
+        // which in turn contains an offset to a value. Both offsets are
+        // relative to the start of the table they appear in.
+        let data = [0xff, 0xff, 0x02, 0x00, 0x01, 0x2a];
+        let mut reader = Buffer::from(&data[..]).reader();
+        reader.read_byte().unwrap();
+
+        let mut table_reader = reader.sub_reader(2, 4).unwrap();
+        assert_eq!(table_reader.relative_offset(), 0);
+        assert_eq!(table_reader.offset().unwrap(), 2);
+
+        let nested_offset = usize::from(table_reader.read_byte().unwrap());
+        let nested_offset = table_reader.buffer().start_offset() + nested_offset;
+        let mut nested_reader = table_reader.sub_reader(nested_offset, 2).unwrap();
+        assert_eq!(nested_reader.offset().unwrap(), 4);
+
+        let value_offset = nested_reader.read_byte().unwrap();
+        nested_reader
+            .set_relative_offset(usize::from(value_offset))
+            .unwrap();
+        assert_eq!(nested_reader.offset().unwrap(), 5);
+        assert_eq!(nested_reader.read_byte().unwrap(), 0x2a);
+        assert!(nested_reader.read_byte().is_err());
+
+        // The parent readers are unaffected
+        assert_eq!(table_reader.relative_offset(), 1);
+        assert_eq!(reader.relative_offset(), 1);
+
+        // Offsets outside of the parent buffer are rejected
+        assert!(table_reader.sub_reader(1, 1).is_err());
+        assert!(table_reader.sub_reader(4, 3).is_err());
+        assert!(reader.sub_reader(2, 5).is_err());
+        assert!(reader.sub_reader(usize::MAX, 1).is_err());
+    }
+
+    #[test]
+    fn read_error_kinds() {
+        let span = Span::Empty;
+        let value = Spanned::empty(Arc::new(Value::ConstLit(Const::Bool(false))));
+        let cases = [
+            (ReadError::InvalidFormat(span), ReadErrorKind::Internal),
+            (ReadError::InvalidValue(span), ReadErrorKind::Internal),
+            (ReadError::UnknownItem, ReadErrorKind::UnknownItem),
+            (ReadError::UnwrappedNone(span), ReadErrorKind::UnwrappedNone),
+            (ReadError::ReadFailFormat(span), ReadErrorKind::Fail),
+            (
+                ReadError::TrailingData(span, 1),
+                ReadErrorKind::TrailingData,
+            ),
+            (ReadError::DepthExceeded(span), ReadErrorKind::DepthExceeded),
+            (ReadError::NoProgress(span), ReadErrorKind::NoProgress),
+            (
+                ReadError::CondFailure(span, value),
+                ReadErrorKind::Predicate,
+            ),
+            (
+                BufferError::UnexpectedEndOfBuffer.with_span(span),
+                ReadErrorKind::Eof,
+            ),
+        ];
+
+        for (error, kind) in cases {
+            assert_eq!(error.kind(), kind, "{error}");
+        }
+    }
+
+    #[test]
+    fn buffer_error_kinds() {
+        let cases = [
+            (
+                BufferError::SetOffsetBeforeStartOfBuffer { offset: 0 },
+                ReadErrorKind::InvalidOffset,
+            ),
+            (
+                BufferError::SetOffsetAfterEndOfBuffer { offset: None },
+                ReadErrorKind::InvalidOffset,
+            ),
+            (BufferError::UnexpectedEndOfBuffer, ReadErrorKind::Eof),
+            (BufferError::PositionOverflow, ReadErrorKind::Overflow),
+            (BufferError::VarIntOverflow, ReadErrorKind::Overflow),
+        ];
+
+        for (error, kind) in cases {
+            assert_eq!(error.kind(), kind, "{error}");
+        }
+    }
+}
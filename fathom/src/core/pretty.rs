@@ -31,7 +31,7 @@ use crate::surface::lexer::is_keyword;
 use crate::symbol::Symbol;
 
 /// Term precedences
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 enum Prec {
     Top = 0,
     Let,
@@ -44,11 +44,21 @@ enum Prec {
 
 const INDENT: isize = 4;
 
-pub struct Context {}
+pub struct Context {
+    /// Annotate each term with the precedence it was printed at.
+    prec_debug: bool,
+}
 
 impl<'arena> Context {
     pub fn new() -> Context {
-        Context {}
+        Context { prec_debug: false }
+    }
+
+    /// Set to true if each term should be annotated with the precedence it
+    /// was printed at. This is useful when debugging the placement of
+    /// parentheses.
+    pub fn set_prec_debug(&mut self, prec_debug: bool) {
+        self.prec_debug = prec_debug;
     }
 
     fn ident(&'arena self, name: Symbol) -> RcDoc {
@@ -121,6 +131,16 @@ impl<'arena> Context {
     }
 
     fn term_prec(&'arena self, prec: Prec, term: &Term<'arena>) -> RcDoc {
+        let doc = self.term_prec_impl(prec, term);
+
+        if self.prec_debug {
+            RcDoc::concat([RcDoc::text(format!("⟨{prec:?}⟩")), doc])
+        } else {
+            doc
+        }
+    }
+
+    fn term_prec_impl(&'arena self, prec: Prec, term: &Term<'arena>) -> RcDoc<'arena> {
         // FIXME: indentation and grouping
 
         match term {
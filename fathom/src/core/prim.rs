@@ -3,7 +3,7 @@ use std::sync::Arc;
 use fxhash::FxHashMap;
 use scoped_arena::Scope;
 
-use crate::core::semantics::{ArcValue, Elim, ElimEnv, Head, Value};
+use crate::core::semantics::{ArcValue, Elim, ElimEnv, Head, Telescope, Value};
 use crate::core::{self, Const, Plicity, Prim, UIntStyle};
 use crate::env::{self, SharedEnv, UniqueEnv};
 use crate::source::{Span, Spanned};
@@ -94,11 +94,54 @@ impl<'arena> Env<'arena> {
         env.define_prim(FormatF32Le, &FORMAT_TYPE);
         env.define_prim(FormatF64Be, &FORMAT_TYPE);
         env.define_prim(FormatF64Le, &FORMAT_TYPE);
+        env.define_prim(FormatFixed16Dot16Be, &FORMAT_TYPE);
+        env.define_prim(FormatFixed16Dot16Le, &FORMAT_TYPE);
+        env.define_prim(FormatFixed2Dot14Be, &FORMAT_TYPE);
+        env.define_prim(FormatFixed2Dot14Le, &FORMAT_TYPE);
+        env.define_prim(FormatULeb128, &FORMAT_TYPE);
+        env.define_prim(FormatSLeb128, &FORMAT_TYPE);
+        env.define_prim_fun(FormatThen, [&FORMAT_TYPE, &FORMAT_TYPE], &FORMAT_TYPE);
         env.define_prim_fun(FormatRepeatLen8, [&U8_TYPE, &FORMAT_TYPE], &FORMAT_TYPE);
         env.define_prim_fun(FormatRepeatLen16, [&U16_TYPE, &FORMAT_TYPE], &FORMAT_TYPE);
         env.define_prim_fun(FormatRepeatLen32, [&U32_TYPE, &FORMAT_TYPE], &FORMAT_TYPE);
         env.define_prim_fun(FormatRepeatLen64, [&U64_TYPE, &FORMAT_TYPE], &FORMAT_TYPE);
+        env.define_prim_fun(FormatRepeatPrefixedU8, [&FORMAT_TYPE], &FORMAT_TYPE);
+        env.define_prim_fun(FormatRepeatPrefixedU16Be, [&FORMAT_TYPE], &FORMAT_TYPE);
+        env.define_prim_fun(FormatRepeatPrefixedU16Le, [&FORMAT_TYPE], &FORMAT_TYPE);
+        env.define_prim_fun(FormatRepeatPrefixedU32Be, [&FORMAT_TYPE], &FORMAT_TYPE);
+        env.define_prim_fun(FormatRepeatPrefixedU32Le, [&FORMAT_TYPE], &FORMAT_TYPE);
+        env.define_prim_fun(FormatRepeatPrefixedU64Be, [&FORMAT_TYPE], &FORMAT_TYPE);
+        env.define_prim_fun(FormatRepeatPrefixedU64Le, [&FORMAT_TYPE], &FORMAT_TYPE);
         env.define_prim_fun(FormatRepeatUntilEnd, [&FORMAT_TYPE], &FORMAT_TYPE);
+        env.define_prim(
+            FormatRepeatUntil,
+            // fun (f : Format) -> (Repr f   -> Bool) -> Format
+            // fun (f : Format) -> (Repr f@0 -> Bool) -> Format
+            &core::Term::FunType(
+                Span::Empty,
+                Plicity::Explicit,
+                env.name("f"),
+                &FORMAT_TYPE,
+                &Term::FunType(
+                    Span::Empty,
+                    Plicity::Explicit,
+                    None,
+                    &Term::FunType(
+                        Span::Empty,
+                        Plicity::Explicit,
+                        None,
+                        &Term::FunApp(
+                            Span::Empty,
+                            Plicity::Explicit,
+                            &Term::Prim(Span::Empty, FormatRepr),
+                            &VAR0,
+                        ),
+                        &BOOL_TYPE,
+                    ),
+                    &FORMAT_TYPE,
+                ),
+            ),
+        );
         env.define_prim_fun(FormatLimit8, [&U8_TYPE, &FORMAT_TYPE], &FORMAT_TYPE);
         env.define_prim_fun(FormatLimit16, [&U16_TYPE, &FORMAT_TYPE], &FORMAT_TYPE);
         env.define_prim_fun(FormatLimit32, [&U32_TYPE, &FORMAT_TYPE], &FORMAT_TYPE);
@@ -126,6 +169,7 @@ impl<'arena> Env<'arena> {
             ),
         );
         env.define_prim(FormatStreamPos, &FORMAT_TYPE);
+        env.define_prim_fun(FormatAlign, [&U64_TYPE], &FORMAT_TYPE);
         env.define_prim(
             FormatSucceed,
             &core::Term::FunType(
@@ -137,6 +181,7 @@ impl<'arena> Env<'arena> {
             ),
         );
         env.define_prim(FormatFail, &FORMAT_TYPE);
+        env.define_prim(FormatAssertEof, &FORMAT_TYPE);
         env.define_prim(
             FormatUnwrap,
             // fun (@A : Type) -> Option A   -> Format
@@ -611,20 +656,37 @@ pub fn repr(prim: Prim) -> Step {
         Prim::FormatF32Le => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::F32Type, [])))),
         Prim::FormatF64Be => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::F64Type, [])))),
         Prim::FormatF64Le => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::F64Type, [])))),
+        Prim::FormatFixed16Dot16Be => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::F64Type, [])))),
+        Prim::FormatFixed16Dot16Le => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::F64Type, [])))),
+        Prim::FormatFixed2Dot14Be => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::F64Type, [])))),
+        Prim::FormatFixed2Dot14Le => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::F64Type, [])))),
+        Prim::FormatULeb128 => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::U64Type, [])))),
+        Prim::FormatSLeb128 => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::S64Type, [])))),
+        Prim::FormatThen => step!(env, [_, format] => env.format_repr(format)),
         Prim::FormatRepeatLen8 => step!(env, [len, elem] => Spanned::empty(Arc::new(Value::prim(Prim::Array8Type, [len.clone(), env.format_repr(elem)])))),
         Prim::FormatRepeatLen16 => step!(env, [len, elem] => Spanned::empty(Arc::new(Value::prim(Prim::Array16Type, [len.clone(), env.format_repr(elem)])))),
         Prim::FormatRepeatLen32 => step!(env, [len, elem] => Spanned::empty(Arc::new(Value::prim(Prim::Array32Type, [len.clone(), env.format_repr(elem)])))),
         Prim::FormatRepeatLen64 => step!(env, [len, elem] => Spanned::empty(Arc::new(Value::prim(Prim::Array64Type, [len.clone(), env.format_repr(elem)])))),
+        Prim::FormatRepeatPrefixedU8 => step!(env, [elem] => Spanned::empty(Arc::new(Value::prim(Prim::ArrayType, [env.format_repr(elem)])))),
+        Prim::FormatRepeatPrefixedU16Be => step!(env, [elem] => Spanned::empty(Arc::new(Value::prim(Prim::ArrayType, [env.format_repr(elem)])))),
+        Prim::FormatRepeatPrefixedU16Le => step!(env, [elem] => Spanned::empty(Arc::new(Value::prim(Prim::ArrayType, [env.format_repr(elem)])))),
+        Prim::FormatRepeatPrefixedU32Be => step!(env, [elem] => Spanned::empty(Arc::new(Value::prim(Prim::ArrayType, [env.format_repr(elem)])))),
+        Prim::FormatRepeatPrefixedU32Le => step!(env, [elem] => Spanned::empty(Arc::new(Value::prim(Prim::ArrayType, [env.format_repr(elem)])))),
+        Prim::FormatRepeatPrefixedU64Be => step!(env, [elem] => Spanned::empty(Arc::new(Value::prim(Prim::ArrayType, [env.format_repr(elem)])))),
+        Prim::FormatRepeatPrefixedU64Le => step!(env, [elem] => Spanned::empty(Arc::new(Value::prim(Prim::ArrayType, [env.format_repr(elem)])))),
         Prim::FormatLimit8 => step!(env, [_, elem] => env.format_repr(elem)),
         Prim::FormatLimit16 => step!(env, [_, elem] => env.format_repr(elem)),
         Prim::FormatLimit32 => step!(env, [_, elem] => env.format_repr(elem)),
         Prim::FormatLimit64 => step!(env, [_, elem] => env.format_repr(elem)),
         Prim::FormatRepeatUntilEnd => step!(env, [elem] => Spanned::empty(Arc::new(Value::prim(Prim::ArrayType, [env.format_repr(elem)])))),
+        Prim::FormatRepeatUntil => step!(env, [elem, _] => Spanned::empty(Arc::new(Value::prim(Prim::ArrayType, [env.format_repr(elem)])))),
         Prim::FormatLink => step!(_, [_, elem] => Spanned::empty(Arc::new(Value::prim(Prim::RefType, [elem.clone()])))),
         Prim::FormatDeref => step!(env, [elem, _] => env.format_repr(elem)),
         Prim::FormatStreamPos => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::PosType, [])))),
+        Prim::FormatAlign => step!(_, [_] => Spanned::empty(Arc::new(Value::RecordType(&[], Telescope::new(SharedEnv::new(), &[]))))),
         Prim::FormatSucceed => step!(_, [elem, _] => elem.clone()),
         Prim::FormatFail => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::VoidType, [])))),
+        Prim::FormatAssertEof => step!(_, [] => Spanned::empty(Arc::new(Value::RecordType(&[], Telescope::new(SharedEnv::new(), &[]))))),
         Prim::FormatUnwrap => step!(_, [elem, _] => elem.clone()),
         Prim::ReportedError => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::ReportedError, [])))),
         _ => |_, _| None,
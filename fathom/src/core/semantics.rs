@@ -544,12 +544,16 @@ impl<'arena, 'env> ElimEnv<'arena, 'env> {
     ///
     /// [beta-reduction]: https://ncatlab.org/nlab/show/beta-reduction
     pub fn record_proj(&self, mut head_expr: ArcValue<'arena>, label: Symbol) -> ArcValue<'arena> {
-        match Arc::make_mut(&mut head_expr) {
-            // Beta-reduction
-            Value::RecordLit(labels, exprs) => (labels.iter())
+        // Beta-reduction, sharing the already evaluated field without cloning
+        // the rest of the record
+        if let Value::RecordLit(labels, exprs) = head_expr.as_ref() {
+            return (labels.iter())
                 .position(|current_label| *current_label == label)
                 .and_then(|expr_index| exprs.get(expr_index).cloned())
-                .unwrap_or_else(|| panic_any(Error::InvalidRecordProj)),
+                .unwrap_or_else(|| panic_any(Error::InvalidRecordProj));
+        }
+
+        match Arc::make_mut(&mut head_expr) {
             // The computation is stuck, preventing further reduction
             Value::Stuck(_, spine) => {
                 spine.push(Elim::RecordProj(label));
@@ -1092,6 +1096,13 @@ impl<'arena, 'env> ConversionEnv<'arena, 'env> {
     /// [computationally equal]: https://ncatlab.org/nlab/show/equality#computational_equality
     /// [eta-conversion]: https://ncatlab.org/nlab/show/eta-conversion
     pub fn is_equal(&mut self, value0: &ArcValue<'_>, value1: &ArcValue<'_>) -> bool {
+        // Check for pointer equality before trying to force the values. This
+        // is a cheap way to compare references to the same item, which share
+        // the value cached in the item environment.
+        if Arc::ptr_eq(value0, value1) {
+            return true;
+        }
+
         let value0 = self.elim_env.force(value0);
         let value1 = self.elim_env.force(value1);
 
@@ -1302,6 +1313,7 @@ impl<'arena, 'env> ConversionEnv<'arena, 'env> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::env::UniqueEnv;
 
     #[allow(dead_code)]
     fn value_has_unify_and_is_equal_impls(value: Value<'_>) {
@@ -1345,6 +1357,64 @@ mod tests {
         }
     }
 
+    #[test]
+    fn record_proj_shares_field() {
+        let item_exprs = UniqueEnv::new();
+        let meta_exprs = UniqueEnv::new();
+        let elim_env = ElimEnv::new(&item_exprs, &meta_exprs);
+
+        let labels = &[Symbol::intern("x"), Symbol::intern("y")];
+        let x = Spanned::empty(Arc::new(Value::ConstLit(Const::Bool(false))));
+        let y = Spanned::empty(Arc::new(Value::ConstLit(Const::Bool(true))));
+        let record = Spanned::empty(Arc::new(Value::RecordLit(
+            labels,
+            vec![x.clone(), y.clone()],
+        )));
+
+        let expr = elim_env.record_proj(record.clone(), labels[1]);
+
+        assert!(Arc::ptr_eq(&expr, &y));
+        assert!(!Arc::ptr_eq(&expr, &x));
+    }
+
+    #[test]
+    fn record_proj_stuck() {
+        let item_exprs = UniqueEnv::new();
+        let meta_exprs = UniqueEnv::new();
+        let elim_env = ElimEnv::new(&item_exprs, &meta_exprs);
+
+        let label = Symbol::intern("x");
+        let head_expr = Spanned::empty(Arc::new(Value::local_var(Level::first())));
+
+        let expr = elim_env.record_proj(head_expr.clone(), label);
+
+        assert!(matches!(head_expr.as_ref(), Value::Stuck(_, spine) if spine.is_empty()));
+        assert!(matches!(
+            expr.as_ref(),
+            Value::Stuck(_, spine) if matches!(spine[..], [Elim::RecordProj(l)] if l == label),
+        ));
+    }
+
+    #[test]
+    fn is_equal_item_references() {
+        let labels = &[Symbol::intern("x")];
+        let value = Value::RecordLit(labels, vec![Spanned::empty(Arc::new(Value::Universe))]);
+        let mut item_exprs = UniqueEnv::new();
+        item_exprs.push(Spanned::empty(Arc::new(value)));
+        let meta_exprs = UniqueEnv::new();
+        let elim_env = ElimEnv::new(&item_exprs, &meta_exprs);
+
+        let term = Term::ItemVar(Span::Empty, Level::first());
+        let mut local_exprs = SharedEnv::new();
+        let expr0 = elim_env.eval_env(&mut local_exprs).eval(&term);
+        let expr1 = elim_env.eval_env(&mut local_exprs).eval(&term);
+
+        assert!(Arc::ptr_eq(&expr0, &expr1));
+        assert!(elim_env
+            .conversion_env(EnvLen::new())
+            .is_equal(&expr0, &expr1));
+    }
+
     #[test]
     #[cfg(target_pointer_width = "64")]
     fn value_size() {
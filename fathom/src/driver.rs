@@ -1,10 +1,12 @@
 use std::cell::RefCell;
-use std::io::Read;
+use std::io::{BufWriter, Read};
 use std::path::Path;
 
 use codespan_reporting::diagnostic::{Diagnostic, Label, Severity};
 use codespan_reporting::files::SimpleFiles;
-use codespan_reporting::term::termcolor::{BufferedStandardStream, ColorChoice, WriteColor};
+use codespan_reporting::term::termcolor::{
+    BufferedStandardStream, ColorChoice, NoColor, WriteColor,
+};
 
 use crate::core::binary::{self, BufferError, ReadError};
 use crate::files::{FileId, Files};
@@ -34,6 +36,14 @@ pub struct Driver<'surface, 'core> {
     core_scope: scoped_arena::Scope<'core>,
 
     allow_errors: bool,
+    dump_unification: bool,
+    prec_debug: bool,
+    explain_type: bool,
+    format_with_tabs: bool,
+    format_trailing_newline: bool,
+    hoist_subterms: Option<usize>,
+    inline_lets: bool,
+    preserve_annotations: bool,
     seen_errors: RefCell<bool>,
     codespan_config: codespan_reporting::term::Config,
     diagnostic_writer: RefCell<Box<dyn WriteColor>>,
@@ -50,6 +60,14 @@ impl<'surface, 'core> Driver<'surface, 'core> {
             files: Files::new(),
 
             allow_errors: false,
+            dump_unification: false,
+            prec_debug: false,
+            explain_type: false,
+            format_with_tabs: false,
+            format_trailing_newline: true,
+            hoist_subterms: None,
+            inline_lets: false,
+            preserve_annotations: false,
             seen_errors: RefCell::new(false),
             codespan_config: codespan_reporting::term::Config::default(),
             diagnostic_writer: RefCell::new(Box::new(BufferedStandardStream::stderr(
@@ -124,6 +142,54 @@ impl<'surface, 'core> Driver<'surface, 'core> {
         self.allow_errors = allow_errors;
     }
 
+    /// Set to true if unification problems should be traced during
+    /// elaboration
+    pub fn set_dump_unification(&mut self, dump_unification: bool) {
+        self.dump_unification = dump_unification;
+    }
+
+    /// Set to true if pretty printed core terms should be annotated with their
+    /// precedences
+    pub fn set_prec_debug(&mut self, prec_debug: bool) {
+        self.prec_debug = prec_debug;
+    }
+
+    /// Set to true if the checking and synthesis of each subterm should be
+    /// traced during elaboration
+    pub fn set_explain_type(&mut self, explain_type: bool) {
+        self.explain_type = explain_type;
+    }
+
+    /// Set to true if formatted source code should be indented with tabs
+    /// instead of spaces
+    pub fn set_format_with_tabs(&mut self, format_with_tabs: bool) {
+        self.format_with_tabs = format_with_tabs;
+    }
+
+    /// Set to true if formatted source code should end with a newline
+    pub fn set_format_trailing_newline(&mut self, format_trailing_newline: bool) {
+        self.format_trailing_newline = format_trailing_newline;
+    }
+
+    /// Set to `Some(min_uses)` if closed subterms that occur at least
+    /// `min_uses` times in normalized terms should be hoisted into let
+    /// bindings
+    pub fn set_hoist_subterms(&mut self, hoist_subterms: Option<usize>) {
+        self.hoist_subterms = hoist_subterms;
+    }
+
+    /// Set to true if let bindings that are used once should be inlined in
+    /// elaborated terms
+    pub fn set_inline_lets(&mut self, inline_lets: bool) {
+        self.inline_lets = inline_lets;
+    }
+
+    /// Set to true if user-written type annotations should be kept in
+    /// elaborated terms
+    pub fn set_preserve_annotations(&mut self, preserve_annotations: bool) {
+        self.preserve_annotations = preserve_annotations;
+    }
+
     /// Set the writer to use when rendering diagnostics
     pub fn set_diagnostic_writer(&mut self, stream: impl 'static + WriteColor) {
         self.diagnostic_writer = RefCell::new(Box::new(stream) as Box<dyn WriteColor>);
@@ -139,6 +205,21 @@ impl<'surface, 'core> Driver<'surface, 'core> {
         self.emit_writer = RefCell::new(Box::new(stream) as Box<dyn WriteColor>);
     }
 
+    /// Set the path of a file to write emitted data and intermediate languages
+    /// to, truncating the file if it already exists
+    pub fn set_emit_path(&mut self, path: &Path) -> Option<()> {
+        match std::fs::File::create(path) {
+            Ok(file) => {
+                self.set_emit_writer(NoColor::new(BufWriter::new(file)));
+                Some(())
+            }
+            Err(error) => {
+                self.emit_write_diagnostic(path.display(), error);
+                None
+            }
+        }
+    }
+
     /// Load a source string into the file database.
     pub fn load_source_string(
         &mut self,
@@ -203,11 +284,14 @@ impl<'surface, 'core> Driver<'surface, 'core> {
 
     pub fn elaborate_and_emit_module(&mut self, file_id: FileId, pretty_core: bool) -> Status {
         let mut context = elaboration::Context::new(file_id, &self.core_scope, ItemEnv::new());
+        context.set_dump_unification(self.dump_unification);
+        context.set_explain_type(self.explain_type);
 
         let surface_module = self.parse_module(file_id);
         let module = context.elab_module(&self.core_scope, &surface_module, &mut |m| {
             self.emit_diagnostic(m.to_diagnostic());
         });
+        self.emit_trace(&mut context);
 
         // Return early if we’ve seen any errors, unless `allow_errors` is enabled
         if *self.seen_errors.borrow() && !self.allow_errors {
@@ -215,10 +299,13 @@ impl<'surface, 'core> Driver<'surface, 'core> {
         }
         if pretty_core {
             self.emit_core_module(&module);
+            return Status::Ok;
         }
 
         self.surface_scope.reset(); // Reuse the surface scope for distillation
-        let context = context.distillation_context(&self.surface_scope);
+        let mut context = context.distillation_context(&self.surface_scope);
+        context.set_inline_lets(self.inline_lets);
+        context.set_preserve_annotations(self.preserve_annotations);
         let module = context.distill_module(&module);
 
         self.emit_module(&module);
@@ -226,22 +313,74 @@ impl<'surface, 'core> Driver<'surface, 'core> {
         Status::Ok
     }
 
-    pub fn elaborate_and_emit_term(&mut self, file_id: FileId) -> Status {
+    pub fn parse_and_emit_module(&mut self, file_id: FileId) -> Status {
+        let surface_module = self.parse_module(file_id);
+
+        if *self.seen_errors.borrow() {
+            return Status::Error;
+        }
+
+        let context = surface::pretty::Context::new(&self.surface_scope);
+        self.emit_formatted_doc(context.module(&surface_module).into_doc());
+
+        Status::Ok
+    }
+
+    pub fn parse_and_emit_term(&mut self, file_id: FileId) -> Status {
+        let surface_term = self.parse_term(file_id);
+
+        if *self.seen_errors.borrow() {
+            return Status::Error;
+        }
+
+        let context = surface::pretty::Context::new(&self.surface_scope);
+        self.emit_formatted_doc(context.term(&surface_term).into_doc());
+
+        Status::Ok
+    }
+
+    pub fn parse_and_emit_item_names(&mut self, file_id: FileId) -> Status {
+        let surface_module = self.parse_module(file_id);
+
+        // Return early if we’ve seen any errors, unless `allow_errors` is enabled
+        if *self.seen_errors.borrow() && !self.allow_errors {
+            return Status::Error;
+        }
+
+        let mut emit_writer = self.emit_writer.borrow_mut();
+        for (kind, label) in surface_module.item_labels() {
+            writeln!(emit_writer, "{} {}", kind.as_str(), label.resolve()).unwrap();
+        }
+        emit_writer.flush().unwrap();
+
+        Status::Ok
+    }
+
+    pub fn elaborate_and_emit_term(&mut self, file_id: FileId, pretty_core: bool) -> Status {
         let mut context = elaboration::Context::new(file_id, &self.core_scope, ItemEnv::new());
+        context.set_dump_unification(self.dump_unification);
+        context.set_explain_type(self.explain_type);
 
         // Parse and elaborate the term
         let surface_term = self.parse_term(file_id);
         let (term, r#type) = context.elab_term(&self.core_scope, &surface_term, &mut |m| {
             self.emit_diagnostic(m.to_diagnostic());
         });
+        self.emit_trace(&mut context);
 
         // Return early if we’ve seen any errors, unless `allow_errors` is enabled
         if *self.seen_errors.borrow() && !self.allow_errors {
             return Status::Error;
         }
+        if pretty_core {
+            self.emit_core_term(&core::Term::Ann(Span::Empty, &term, &r#type));
+            return Status::Ok;
+        }
 
         self.surface_scope.reset(); // Reuse the surface scope for distillation
         let mut context = context.distillation_context(&self.surface_scope);
+        context.set_inline_lets(self.inline_lets);
+        context.set_preserve_annotations(self.preserve_annotations);
         let term = context.check(&term);
         let r#type = context.check(&r#type);
 
@@ -250,14 +389,58 @@ impl<'surface, 'core> Driver<'surface, 'core> {
         Status::Ok
     }
 
+    pub fn normalize_and_emit_module(&mut self, file_id: FileId) -> Status {
+        let mut context = elaboration::Context::new(file_id, &self.core_scope, ItemEnv::new());
+        context.set_dump_unification(self.dump_unification);
+        context.set_explain_type(self.explain_type);
+
+        let surface_module = self.parse_module(file_id);
+        let module = context.elab_module(&self.core_scope, &surface_module, &mut |m| {
+            self.emit_diagnostic(m.to_diagnostic());
+        });
+        self.emit_trace(&mut context);
+
+        // Return early if we’ve seen any errors, unless `allow_errors` is enabled
+        if *self.seen_errors.borrow() && !self.allow_errors {
+            return Status::Error;
+        }
+
+        let core_scope = &self.core_scope;
+        let items = core_scope.to_scope_from_iter(module.items.iter().map(|item| match item {
+            core::Item::Def {
+                label,
+                r#type,
+                expr,
+            } => core::Item::Def {
+                label: *label,
+                r#type: core_scope.to_scope(context.eval_env().normalize(core_scope, r#type)),
+                expr: core_scope.to_scope(context.eval_env().normalize(core_scope, expr)),
+            },
+        }));
+        let module = core::Module { items };
+
+        self.surface_scope.reset(); // Reuse the surface scope for distillation
+        let mut context = context.distillation_context(&self.surface_scope);
+        context.set_eta_contract(true);
+        context.set_hoist_subterms(self.hoist_subterms);
+        let module = context.distill_module(&module);
+
+        self.emit_module(&module);
+
+        Status::Ok
+    }
+
     pub fn normalize_and_emit_term(&mut self, file_id: FileId) -> Status {
         let mut context = elaboration::Context::new(file_id, &self.core_scope, ItemEnv::new());
+        context.set_dump_unification(self.dump_unification);
+        context.set_explain_type(self.explain_type);
 
         // Parse and elaborate the term
         let surface_term = self.parse_term(file_id);
         let (term, r#type) = context.elab_term(&self.core_scope, &surface_term, &mut |m| {
             self.emit_diagnostic(m.to_diagnostic());
         });
+        self.emit_trace(&mut context);
 
         // Return early if we’ve seen any errors, unless `allow_errors` is enabled
         if *self.seen_errors.borrow() && !self.allow_errors {
@@ -269,6 +452,8 @@ impl<'surface, 'core> Driver<'surface, 'core> {
 
         self.surface_scope.reset(); // Reuse the surface scope for distillation
         let mut context = context.distillation_context(&self.surface_scope);
+        context.set_eta_contract(true);
+        context.set_hoist_subterms(self.hoist_subterms);
         let term = context.check(&term);
         let r#type = context.check(&r#type);
 
@@ -292,10 +477,13 @@ impl<'surface, 'core> Driver<'surface, 'core> {
         // Parse and elaborate a module if one was provided
         if let Some(file_id) = module_file_id {
             let mut elab_context = elaboration::Context::new(file_id, &self.core_scope, item_env);
+            elab_context.set_dump_unification(self.dump_unification);
+            elab_context.set_explain_type(self.explain_type);
             let surface_module = self.parse_module(file_id);
             let module = elab_context.elab_module(&self.core_scope, &surface_module, &mut |m| {
                 self.emit_diagnostic(m.to_diagnostic());
             });
+            self.emit_trace(&mut elab_context);
             // Add it to the binary context
             binary_context.add_module(&module);
             item_env = elab_context.finish();
@@ -307,10 +495,13 @@ impl<'surface, 'core> Driver<'surface, 'core> {
         // it works for now!
         let mut elab_context =
             elaboration::Context::new(format_file_id, &self.core_scope, item_env);
+        elab_context.set_dump_unification(self.dump_unification);
+        elab_context.set_explain_type(self.explain_type);
         let surface_format = self.parse_term(format_file_id);
         let format = elab_context.elab_format(&self.core_scope, &surface_format, &mut |m| {
             self.emit_diagnostic(m.to_diagnostic());
         });
+        self.emit_trace(&mut elab_context);
 
         // Return early if we’ve seen any errors, unless `allow_errors` is enabled
         if *self.seen_errors.borrow() && !self.allow_errors {
@@ -366,7 +557,8 @@ impl<'surface, 'core> Driver<'surface, 'core> {
     }
 
     fn emit_core_module(&self, module: &core::Module<'_>) {
-        let context = core::pretty::Context::new();
+        let mut context = core::pretty::Context::new();
+        context.set_prec_debug(self.prec_debug);
         // TODO: Ideally this would be a call to emit_doc
         let doc = context.module(module);
         let mut emit_writer = self.emit_writer.borrow_mut();
@@ -374,6 +566,16 @@ impl<'surface, 'core> Driver<'surface, 'core> {
         emit_writer.flush().unwrap();
     }
 
+    fn emit_core_term(&self, term: &core::Term<'_>) {
+        let mut context = core::pretty::Context::new();
+        context.set_prec_debug(self.prec_debug);
+        // TODO: Ideally this would be a call to emit_doc
+        let doc = context.term(term);
+        let mut emit_writer = self.emit_writer.borrow_mut();
+        writeln!(emit_writer, "{}", doc.pretty(self.emit_width)).unwrap();
+        emit_writer.flush().unwrap();
+    }
+
     fn emit_term(&self, term: &surface::Term<'_, ()>) {
         let context = surface::pretty::Context::new(&self.surface_scope);
         self.emit_doc(context.term(term).into_doc());
@@ -409,6 +611,20 @@ impl<'surface, 'core> Driver<'surface, 'core> {
         emit_writer.flush().unwrap();
     }
 
+    fn emit_formatted_doc(&self, doc: pretty::RefDoc) {
+        let mut output = doc.pretty(self.emit_width).to_string();
+        if self.format_with_tabs {
+            output = indent_with_tabs(&output);
+        }
+        if self.format_trailing_newline {
+            output.push('\n');
+        }
+
+        let mut emit_writer = self.emit_writer.borrow_mut();
+        write!(emit_writer, "{output}").unwrap();
+        emit_writer.flush().unwrap();
+    }
+
     fn emit_diagnostic(&self, diagnostic: Diagnostic<FileId>) {
         let mut writer = self.diagnostic_writer.borrow_mut();
         let config = &self.codespan_config;
@@ -421,6 +637,13 @@ impl<'surface, 'core> Driver<'surface, 'core> {
         }
     }
 
+    /// Emit the lines of the elaboration trace, if tracing is enabled.
+    fn emit_trace(&self, context: &mut elaboration::Context<'_>) {
+        let mut writer = self.diagnostic_writer.borrow_mut();
+        context.handle_trace(&mut |line| writeln!(writer, "{line}").unwrap());
+        writer.flush().unwrap();
+    }
+
     fn emit_diagnostics(&self, diagnostics: impl Iterator<Item = Diagnostic<FileId>>) {
         for diagnostic in diagnostics {
             self.emit_diagnostic(diagnostic);
@@ -433,6 +656,12 @@ impl<'surface, 'core> Driver<'surface, 'core> {
         self.emit_diagnostic(diagnostic);
     }
 
+    fn emit_write_diagnostic(&self, name: impl std::fmt::Display, error: std::io::Error) {
+        let diagnostic =
+            Diagnostic::error().with_message(format!("couldn't write `{name}`: {error}"));
+        self.emit_diagnostic(diagnostic);
+    }
+
     fn emit_source_diagnostic(&self, name: impl std::fmt::Display, error: SourceTooBig) {
         let diagnostic = Diagnostic::error().with_message(format!(
             "could't read `{name}`: source too big (source is {} bytes, max length is {} bytes)",
@@ -453,6 +682,24 @@ impl<'surface, 'core> Driver<'surface, 'core> {
                 .with_notes(vec![format!(
                     "A fail format was encountered when reading this file."
                 )]),
+            ReadError::TrailingData(span, remaining) => Diagnostic::error()
+                .with_message(err.to_string())
+                .with_labels(label_for_span(&span).into_iter().collect())
+                .with_notes(vec![format!(
+                    "The end of the buffer was asserted with {remaining} byte(s) remaining."
+                )]),
+            ReadError::DepthExceeded(span) => Diagnostic::error()
+                .with_message(err.to_string())
+                .with_labels(label_for_span(&span).into_iter().collect())
+                .with_notes(vec![format!(
+                    "Formats were nested more deeply than the maximum read depth."
+                )]),
+            ReadError::NoProgress(span) => Diagnostic::error()
+                .with_message(err.to_string())
+                .with_labels(label_for_span(&span).into_iter().collect())
+                .with_notes(vec![format!(
+                    "An element of the repeated format was read without consuming any data."
+                )]),
             ReadError::CondFailure(span, ref value) => {
                 let core_scope = &self.core_scope;
                 let surface_scope = &self.surface_scope;
@@ -515,6 +762,12 @@ impl<'surface, 'core> Driver<'surface, 'core> {
                 .with_notes(vec![format!(
                     "The offset is beyond the end of the buffer (overflow).",
                 )]),
+            BufferError::VarIntOverflow => Diagnostic::error()
+                .with_message(err.to_string())
+                .with_labels(label_for_span(&span).into_iter().collect())
+                .with_notes(vec![format!(
+                    "The encoded integer does not fit in 64 bits."
+                )]),
             BufferError::PositionOverflow => Diagnostic::bug()
                 .with_message(format!("unexpected error '{err}'"))
                 .with_labels(label_for_span(&span).into_iter().collect())
@@ -531,3 +784,28 @@ fn label_for_span(span: &Span) -> Option<Label<FileId>> {
         Span::Empty => None,
     }
 }
+
+/// Replace the leading spaces of each line with tabs, one for each level of
+/// indentation.
+fn indent_with_tabs(source: &str) -> String {
+    let indent = surface::pretty::INDENT as usize;
+    let lines = source.split('\n').map(|line| {
+        let spaces = line.len() - line.trim_start_matches(' ').len();
+        let tabs = "\t".repeat(spaces / indent);
+        format!("{tabs}{}", &line[(spaces - spaces % indent)..])
+    });
+
+    lines.collect::<Vec<_>>().join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indent_with_tabs_replaces_leading_spaces() {
+        let source = "def main = {\n    a <- {\n        b <- u8,\n     },\n};";
+        let expected = "def main = {\n\ta <- {\n\t\tb <- u8,\n\t },\n};";
+        assert_eq!(indent_with_tabs(source), expected);
+    }
+}
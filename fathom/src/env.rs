@@ -41,7 +41,7 @@ type RawVar = usize;
 ///
 /// [de Bruijn index]: https://en.wikipedia.org/wiki/De_Bruijn_index
 /// [alpha-equivalence]: https://ncatlab.org/nlab/show/alpha-equivalence
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Index(RawVar);
 
 impl Index {
@@ -88,7 +88,7 @@ pub fn indices() -> impl Iterator<Item = Index> {
 /// are not tied to a specific binding depth, unlike [indices][Index].
 /// Because of this, we're able to sidestep the need for expensive variable
 /// shifting during [normalization][crate::core::semantics::EvalEnv::normalize].
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Level(RawVar);
 
 impl Level {
@@ -26,21 +26,83 @@ enum Cli {
             display_order = 1
         )]
         term_file: Option<PathOrStdin>,
-        /// Continue even if errors were encountered
-        #[clap(long = "allow-errors")]
-        allow_errors: bool,
-        /// Pretty print core module
-        #[clap(long = "pretty-core", conflicts_with("TERM_FILE"))]
+        /// Pretty print the core module or term, instead of the surface syntax
+        #[clap(long = "pretty-core")]
         pretty_core: bool,
+        /// Annotate the pretty printed core syntax with term precedences
+        #[clap(long = "prec-debug", requires = "pretty_core")]
+        prec_debug: bool,
+        /// List the names of the items in the module, without elaborating it
+        #[clap(long = "list-items", conflicts_with_all(["TERM_FILE", "pretty_core"]))]
+        list_items: bool,
+        /// Inline let bindings that are only used once
+        #[clap(long = "inline-lets")]
+        inline_lets: bool,
+        /// Keep the type annotations written in the source
+        #[clap(long = "preserve-annotations")]
+        preserve_annotations: bool,
+        #[clap(flatten)]
+        elab_options: ElabOptions,
+        #[clap(flatten)]
+        common_options: CommonOptions,
     },
-    /// Normalize a Fathom term, printing its normal form and type
+    /// Normalize a Fathom module or term, printing its normal form and type
     Norm {
+        /// Path to a module to normalize
+        #[clap(
+            long = "module",
+            name = "MODULE_FILE",
+            group = "input",
+            required_unless_present = "input",
+            display_order = 0
+        )]
+        module_file: Option<PathOrStdin>,
         /// Path to a term to normalize
-        #[clap(long = "term", name = "TERM_FILE", display_order = 0)]
-        term_file: PathOrStdin,
-        /// Continue even if errors were encountered
-        #[clap(long = "allow-errors")]
-        allow_errors: bool,
+        #[clap(
+            long = "term",
+            name = "TERM_FILE",
+            group = "input",
+            required_unless_present = "input",
+            display_order = 1
+        )]
+        term_file: Option<PathOrStdin>,
+        /// Hoist closed subterms that occur at least MIN_USES times into let
+        /// bindings
+        #[clap(long = "hoist-subterms", name = "MIN_USES")]
+        hoist_subterms: Option<usize>,
+        #[clap(flatten)]
+        elab_options: ElabOptions,
+        #[clap(flatten)]
+        common_options: CommonOptions,
+    },
+    /// Format a Fathom module or term, printing the result to stdout
+    Fmt {
+        /// Path to a module to format
+        #[clap(
+            long = "module",
+            name = "MODULE_FILE",
+            group = "input",
+            required_unless_present = "input",
+            display_order = 0
+        )]
+        module_file: Option<PathOrStdin>,
+        /// Path to a term to format
+        #[clap(
+            long = "term",
+            name = "TERM_FILE",
+            group = "input",
+            required_unless_present = "input",
+            display_order = 1
+        )]
+        term_file: Option<PathOrStdin>,
+        /// Indent with tabs instead of spaces
+        #[clap(long = "tabs")]
+        tabs: bool,
+        /// Do not end the output with a newline
+        #[clap(long = "no-trailing-newline")]
+        no_trailing_newline: bool,
+        #[clap(flatten)]
+        common_options: CommonOptions,
     },
     /// Manipulate binary data based on a Fathom format
     #[clap(after_help = DATA_COMMAND_AFTER_HELP)]
@@ -65,12 +127,62 @@ enum Cli {
         /// Path to the binary data to read from
         #[clap(name = "BINARY_FILE")]
         binary_file: PathOrStdin,
-        /// Continue even if errors were encountered
-        #[clap(long = "allow-errors")]
-        allow_errors: bool,
+        #[clap(flatten)]
+        elab_options: ElabOptions,
+        #[clap(flatten)]
+        common_options: CommonOptions,
     },
 }
 
+/// Options shared by all subcommands
+#[derive(clap::Args)]
+struct CommonOptions {
+    /// Path to write the output to, instead of stdout
+    #[clap(short = 'o', long = "output", name = "OUTPUT_FILE", display_order = 2)]
+    output_file: Option<PathBuf>,
+    /// Use the default panic handler, without reporting panics as bugs
+    #[clap(long = "no-panic-hook")]
+    no_panic_hook: bool,
+}
+
+impl CommonOptions {
+    /// Create a driver configured with these options, exiting if the output
+    /// file could not be created
+    fn driver<'surface, 'core>(self) -> fathom::Driver<'surface, 'core> {
+        let mut driver = fathom::Driver::new();
+        if !self.no_panic_hook {
+            driver.install_panic_hook();
+        }
+        driver.set_emit_width(get_pretty_width());
+        if let Some(output_file) = self.output_file {
+            unwrap_or_exit(driver.set_emit_path(&output_file));
+        }
+        driver
+    }
+}
+
+/// Options shared by subcommands that elaborate Fathom code
+#[derive(clap::Args)]
+struct ElabOptions {
+    /// Continue even if errors were encountered
+    #[clap(long = "allow-errors")]
+    allow_errors: bool,
+    /// Log unification problems and their solutions to stderr
+    #[clap(long = "dump-unification")]
+    dump_unification: bool,
+    /// Log the checking and synthesis of each subterm to stderr
+    #[clap(long = "explain-type")]
+    explain_type: bool,
+}
+
+impl ElabOptions {
+    fn apply(self, driver: &mut fathom::Driver<'_, '_>) {
+        driver.set_allow_errors(self.allow_errors);
+        driver.set_dump_unification(self.dump_unification);
+        driver.set_explain_type(self.explain_type);
+    }
+}
+
 const DATA_COMMAND_AFTER_HELP: &str = "\
 Examples:
 
@@ -150,22 +262,32 @@ fn main() -> ! {
         Cli::Elab {
             module_file,
             term_file,
-            allow_errors,
             pretty_core,
+            prec_debug,
+            list_items,
+            inline_lets,
+            preserve_annotations,
+            elab_options,
+            common_options,
         } => {
-            let mut driver = fathom::Driver::new();
-            driver.install_panic_hook();
-            driver.set_allow_errors(allow_errors);
-            driver.set_emit_width(get_pretty_width());
+            let mut driver = common_options.driver();
+            elab_options.apply(&mut driver);
+            driver.set_prec_debug(prec_debug);
+            driver.set_inline_lets(inline_lets);
+            driver.set_preserve_annotations(preserve_annotations);
 
             let status = match (module_file, term_file) {
                 (Some(module_file), None) => {
                     let file_id = load_file_or_exit(&mut driver, module_file);
-                    driver.elaborate_and_emit_module(file_id, pretty_core)
+                    if list_items {
+                        driver.parse_and_emit_item_names(file_id)
+                    } else {
+                        driver.elaborate_and_emit_module(file_id, pretty_core)
+                    }
                 }
                 (None, Some(term_file)) => {
                     let file_id = load_file_or_exit(&mut driver, term_file);
-                    driver.elaborate_and_emit_term(file_id)
+                    driver.elaborate_and_emit_term(file_id, pretty_core)
                 }
                 (Some(_), Some(_)) | (None, None) => {
                     unreachable!(r#"guarded by `required_unless_present = "input"`"#)
@@ -175,16 +297,56 @@ fn main() -> ! {
             std::process::exit(status.exit_code());
         }
         Cli::Norm {
+            module_file,
             term_file,
-            allow_errors,
+            hoist_subterms,
+            elab_options,
+            common_options,
         } => {
-            let mut driver = fathom::Driver::new();
-            driver.install_panic_hook();
-            driver.set_allow_errors(allow_errors);
-            driver.set_emit_width(get_pretty_width());
+            let mut driver = common_options.driver();
+            elab_options.apply(&mut driver);
+            driver.set_hoist_subterms(hoist_subterms);
 
-            let file_id = load_file_or_exit(&mut driver, term_file);
-            let status = driver.normalize_and_emit_term(file_id);
+            let status = match (module_file, term_file) {
+                (Some(module_file), None) => {
+                    let file_id = load_file_or_exit(&mut driver, module_file);
+                    driver.normalize_and_emit_module(file_id)
+                }
+                (None, Some(term_file)) => {
+                    let file_id = load_file_or_exit(&mut driver, term_file);
+                    driver.normalize_and_emit_term(file_id)
+                }
+                (Some(_), Some(_)) | (None, None) => {
+                    unreachable!(r#"guarded by `required_unless_present = "input"`"#)
+                }
+            };
+
+            std::process::exit(status.exit_code());
+        }
+        Cli::Fmt {
+            module_file,
+            term_file,
+            tabs,
+            no_trailing_newline,
+            common_options,
+        } => {
+            let mut driver = common_options.driver();
+            driver.set_format_with_tabs(tabs);
+            driver.set_format_trailing_newline(!no_trailing_newline);
+
+            let status = match (module_file, term_file) {
+                (Some(module_file), None) => {
+                    let file_id = load_file_or_exit(&mut driver, module_file);
+                    driver.parse_and_emit_module(file_id)
+                }
+                (None, Some(term_file)) => {
+                    let file_id = load_file_or_exit(&mut driver, term_file);
+                    driver.parse_and_emit_term(file_id)
+                }
+                (Some(_), Some(_)) | (None, None) => {
+                    unreachable!(r#"guarded by `required_unless_present = "input"`"#)
+                }
+            };
 
             std::process::exit(status.exit_code());
         }
@@ -192,12 +354,11 @@ fn main() -> ! {
             module_file,
             format,
             binary_file,
-            allow_errors,
+            elab_options,
+            common_options,
         } => {
-            let mut driver = fathom::Driver::new();
-            driver.install_panic_hook();
-            driver.set_allow_errors(allow_errors);
-            driver.set_emit_width(get_pretty_width());
+            let mut driver = common_options.driver();
+            elab_options.apply(&mut driver);
 
             let module_file_id = module_file.map(|input| load_file_or_exit(&mut driver, input));
             let format_file_id = load_source_or_exit(&mut driver, "<FORMAT>".to_owned(), format);
@@ -38,14 +38,25 @@ impl<'arena> Module<'arena, ByteRange> {
         let mut messages = Vec::new();
 
         let tokens = lexer::tokens(source);
-        let term = grammar::ModuleParser::new()
+        let module = grammar::ModuleParser::new()
             .parse(scope, &mut messages, tokens)
             .unwrap_or_else(|error| {
                 messages.push(ParseMessage::from_lalrpop(error));
                 Module { items: &[] }
             });
 
-        (term, messages)
+        (module, messages)
+    }
+}
+
+impl<'arena, Range> Module<'arena, Range> {
+    /// The kinds and labels of the definitions in the module, in source
+    /// order. Items that failed to parse are skipped.
+    pub fn item_labels(&self) -> impl Iterator<Item = (ItemKind, Symbol)> + '_ {
+        self.items.iter().filter_map(|item| match item {
+            Item::Def(item) => Some((item.kind, item.label.1)),
+            Item::ReportedError(_) => None,
+        })
     }
 }
 
@@ -58,11 +69,35 @@ pub enum Item<'arena, Range> {
     ReportedError(Range),
 }
 
+/// The keyword that introduced a top-level definition.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ItemKind {
+    /// Definitions introduced with `def`
+    Def,
+    /// Type definitions introduced with `type`, which are annotated with
+    /// `Type` during parsing
+    Type,
+}
+
+impl ItemKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ItemKind::Def => "def",
+            ItemKind::Type => "type",
+        }
+    }
+}
+
 /// Top-level definitions
 #[derive(Debug, Clone)]
 pub struct ItemDef<'arena, Range> {
     /// The full range of the definition
     range: Range,
+    /// The keyword that introduced the definition
+    kind: ItemKind,
+    /// The lines of the doc comment preceding this definition, without their
+    /// leading `///`
+    doc_comment: &'arena [Symbol],
     /// The label that identifies this definition
     label: (Range, Symbol),
     /// Parameter patterns
@@ -71,6 +106,45 @@ pub struct ItemDef<'arena, Range> {
     r#type: Option<&'arena Term<'arena, Range>>,
     /// The defined expression
     expr: &'arena Term<'arena, Range>,
+    /// Local definitions in the `where` clause following the defined
+    /// expression
+    where_bindings: &'arena [WhereBinding<'arena, Range>],
+}
+
+impl<'arena, Range: Clone> ItemDef<'arena, Range> {
+    /// Desugar the `where` clause of the definition into `let` expressions
+    /// around the defined expression.
+    fn desugar_where<'out_arena>(
+        &self,
+        scope: &'out_arena Scope<'out_arena>,
+    ) -> &'out_arena Term<'out_arena, Range>
+    where
+        'arena: 'out_arena,
+    {
+        (self.where_bindings.iter()).rfold(self.expr, |body_expr, binding| {
+            scope.to_scope(Term::Let(
+                binding.range.clone(),
+                binding.pattern.clone(),
+                binding.r#type,
+                binding.expr,
+                body_expr,
+            ))
+        })
+    }
+}
+
+/// A local definition in the `where` clause of a top-level definition, eg.
+/// `elem = u16be;`
+#[derive(Debug, Clone)]
+pub struct WhereBinding<'arena, Range> {
+    /// The full range of the binding
+    range: Range,
+    /// The pattern that binds the definition
+    pattern: Pattern<Range>,
+    /// An optional type annotation for the defined expression
+    r#type: Option<&'arena Term<'arena, Range>>,
+    /// The defined expression
+    expr: &'arena Term<'arena, Range>,
 }
 
 /// Surface patterns.
@@ -108,6 +182,7 @@ pub enum BinOp<Range> {
     Lte(Range),
     Gt(Range),
     Gte(Range),
+    Then(Range),
 }
 
 impl<Range> BinOp<Range> {
@@ -125,7 +200,8 @@ impl<Range> BinOp<Range> {
             | BinOp::Lt(range)
             | BinOp::Lte(range)
             | BinOp::Gt(range)
-            | BinOp::Gte(range) => range.clone(),
+            | BinOp::Gte(range)
+            | BinOp::Then(range) => range.clone(),
         }
     }
 
@@ -141,6 +217,7 @@ impl<Range> BinOp<Range> {
             BinOp::Lte(_) => "<=",
             BinOp::Gt(_) => ">",
             BinOp::Gte(_) => ">=",
+            BinOp::Then(_) => ">>",
         }
     }
 
@@ -156,6 +233,7 @@ impl<Range> BinOp<Range> {
             BinOp::Lte(range) => BinOp::Lte(f(range)),
             BinOp::Gt(range) => BinOp::Gt(f(range)),
             BinOp::Gte(range) => BinOp::Gte(f(range)),
+            BinOp::Then(range) => BinOp::Then(f(range)),
         }
     }
 }
@@ -261,6 +339,11 @@ pub enum Term<'arena, Range> {
     /// These are stored as strings, and will be parsed during [elaboration]
     /// once the target type is known.
     StringLiteral(Range, Symbol),
+    /// Byte string literals, written as hexadecimal digits, eg. `0x"89504E47"`.
+    ///
+    /// These are stored as strings, and will be parsed during [elaboration]
+    /// into arrays of bytes.
+    ByteStringLiteral(Range, Symbol),
     /// Number literals.
     ///
     /// These are stored as strings, and will be parsed during [elaboration]
@@ -313,6 +396,7 @@ impl<'arena, Range: Clone> Term<'arena, Range> {
             | Term::Proj(range, _, _)
             | Term::ArrayLiteral(range, _)
             | Term::StringLiteral(range, _)
+            | Term::ByteStringLiteral(range, _)
             | Term::NumberLiteral(range, _)
             | Term::BooleanLiteral(range, _)
             | Term::FormatRecord(range, _)
@@ -531,6 +615,73 @@ mod tests {
         assert_eq!(std::mem::size_of::<Term<ByteRange>>(), 48);
     }
 
+    #[test]
+    fn doc_comments() {
+        let doc_comments = |source: &str| {
+            let scope = Scope::new();
+            let source = ProgramSource::try_from(source.to_owned()).unwrap();
+            let (module, messages) = Module::parse(&scope, &source);
+            assert!(messages.is_empty());
+
+            (module.items.iter())
+                .map(|item| match item {
+                    Item::Def(item) => (item.doc_comment.iter())
+                        .map(|line| line.resolve().to_owned())
+                        .collect::<Vec<_>>(),
+                    Item::ReportedError(_) => panic!("unexpected error item"),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            doc_comments("/// a\n///\n  /// b\ndef x = 1;"),
+            [[" a", "", " b"]]
+        );
+        assert_eq!(doc_comments("/// a\ntype x = U8;"), [[" a"]]);
+        assert_eq!(doc_comments("// a\ndef x = 1;"), [Vec::<&str>::new()]);
+        assert_eq!(doc_comments("//// a\ndef x = 1;"), [Vec::<&str>::new()]);
+        assert_eq!(doc_comments("/// a\n// b\ndef x = 1;"), [[" a"]]);
+        // Doc comments that do not precede an item are ignored
+        assert_eq!(
+            doc_comments("def x = {\n/// a\n y <- u8 };\n/// b\n def z = 1;"),
+            [vec![], vec![" b"]],
+        );
+    }
+
+    #[test]
+    fn bin_op_precedence() {
+        fn parenthesize(term: &Term<'_, ByteRange>) -> String {
+            match term {
+                Term::Name(_, name) => name.resolve().to_owned(),
+                Term::Paren(_, term) => parenthesize(term),
+                Term::BinOp(_, lhs, op, rhs) => {
+                    format!("({} {} {})", parenthesize(lhs), op, parenthesize(rhs))
+                }
+                _ => panic!("unexpected term"),
+            }
+        }
+
+        let parse = |source: &str| {
+            let scope = Scope::new();
+            let source = ProgramSource::try_from(source.to_owned()).unwrap();
+            let (term, messages) = Term::parse(&scope, &source);
+            messages.is_empty().then(|| parenthesize(&term))
+        };
+
+        assert_eq!(parse("a + b * c").unwrap(), "(a + (b * c))");
+        assert_eq!(parse("a * b + c").unwrap(), "((a * b) + c)");
+        assert_eq!(parse("(a + b) * c").unwrap(), "((a + b) * c)");
+        assert_eq!(parse("a - b - c").unwrap(), "((a - b) - c)");
+        assert_eq!(parse("a / b / c").unwrap(), "((a / b) / c)");
+        assert_eq!(parse("a + b < c * d").unwrap(), "((a + b) < (c * d))");
+        assert_eq!(parse("a < b == c >= d").unwrap(), "((a < b) == (c >= d))");
+        assert_eq!(parse("a >> b >> c").unwrap(), "(a >> (b >> c))");
+
+        // Comparisons are non-associative
+        assert_eq!(parse("a < b < c"), None);
+        assert_eq!(parse("a == b != c"), None);
+    }
+
     #[test]
     #[cfg(target_pointer_width = "64")]
     fn pattern_size() {
@@ -1,5 +1,8 @@
 //! Bidirectional distillation of the core language into the surface language.
 
+use std::hash::Hasher;
+
+use fxhash::{FxHashMap, FxHasher};
 use scoped_arena::Scope;
 
 use crate::alloc::SliceVec;
@@ -9,7 +12,8 @@ use crate::env::{self, EnvLen, Index, Level, UniqueEnv};
 use crate::source::Span;
 use crate::surface::elaboration::MetaSource;
 use crate::surface::{
-    Arg, BinOp, ExprField, FormatField, Item, ItemDef, Module, Param, Pattern, Term, TypeField,
+    Arg, BinOp, ExprField, FormatField, Item, ItemDef, ItemKind, Module, Param, Pattern, Term,
+    TypeField,
 };
 use crate::symbol::Symbol;
 
@@ -19,10 +23,11 @@ enum Prec {
     Top = 0,
     Let,
     Fun,
+    Then,
     Eq,
     Cmp,
-    Mul,
     Add,
+    Mul,
     App,
     Proj,
     Atomic,
@@ -44,6 +49,44 @@ pub struct Context<'arena, 'env> {
     local_names: UniqueEnv<Option<Symbol>>,
     /// Metavariable sources.
     meta_sources: &'env UniqueEnv<MetaSource>,
+    /// Eta-contract function literals, eg. `fun x => f x` to `f`.
+    eta_contract: bool,
+    /// Inline let bindings that are only used once, eg. `let x = f a; g x` to
+    /// `g (f a)`.
+    inline_lets: bool,
+    /// Let bindings that are currently being inlined.
+    inlined_locals: Vec<InlinedLocal<'arena>>,
+    /// Hoist closed subterms that occur at least this many times into let
+    /// bindings, eg. `f (g a) (g a)` to `let x = g a; f x x`.
+    hoist_subterms: Option<usize>,
+    /// The occurrences of subterms that are currently being hoisted, along
+    /// with the names they are bound to.
+    hoisted_subterms: Vec<(*const (), Symbol)>,
+    /// Preserve type annotations in checkable positions, eg. `let x : Type =
+    /// (U8 : Type); x`, rather than erasing them.
+    preserve_annotations: bool,
+}
+
+/// A closed subterm that might be hoisted into a let binding.
+struct Subterm<'core> {
+    /// The first occurrence of the subterm.
+    term: &'core core::Term<'core>,
+    /// The occurrences of the subterm, along with the subterms that enclose
+    /// each occurrence.
+    occurrences: Vec<(*const (), Vec<usize>)>,
+}
+
+/// A let binding that is being inlined at its use site.
+struct InlinedLocal<'arena> {
+    /// The level of the let binding in the local environment.
+    level: Level,
+    /// The distilled definition of the let binding.
+    expr: Term<'arena, ()>,
+    /// The names of the locals referred to by the definition.
+    free_names: Vec<Symbol>,
+    /// Set to true if a local bound between the let binding and its use site
+    /// would capture one of the `free_names`.
+    captured: bool,
 }
 
 impl<'arena, 'env> Context<'arena, 'env> {
@@ -59,12 +102,44 @@ impl<'arena, 'env> Context<'arena, 'env> {
             item_names,
             local_names,
             meta_sources,
+            eta_contract: false,
+            inline_lets: false,
+            inlined_locals: Vec::new(),
+            hoist_subterms: None,
+            hoisted_subterms: Vec::new(),
+            preserve_annotations: false,
         }
     }
 
+    /// Set to true if function literals should be eta-contracted where
+    /// possible, eg. `fun x => f x` to `f`.
+    pub fn set_eta_contract(&mut self, eta_contract: bool) {
+        self.eta_contract = eta_contract;
+    }
+
+    /// Set to true if let bindings that are only used once should be inlined
+    /// at their use site, eg. `let x = f a; g x` to `g (f a)`.
+    pub fn set_inline_lets(&mut self, inline_lets: bool) {
+        self.inline_lets = inline_lets;
+    }
+
+    /// Set to `Some(min_uses)` if closed subterms that occur at least
+    /// `min_uses` times should be hoisted into let bindings, eg.
+    /// `f (g a) (g a)` to `let x = g a; f x x`.
+    pub fn set_hoist_subterms(&mut self, min_uses: Option<usize>) {
+        self.hoist_subterms = min_uses;
+    }
+
+    /// Set to true if type annotations should be preserved in checkable
+    /// positions, where they would otherwise be erased.
+    pub fn set_preserve_annotations(&mut self, preserve_annotations: bool) {
+        self.preserve_annotations = preserve_annotations;
+    }
+
     fn is_bound(&self, name: Symbol) -> bool {
         (self.local_names.iter()).any(|local_name| *local_name == Some(name))
             || self.item_names.iter().any(|item_name| *item_name == name)
+            || (self.hoisted_subterms.iter()).any(|(_, hoisted_name)| *hoisted_name == name)
     }
 
     fn local_len(&mut self) -> EnvLen {
@@ -101,6 +176,29 @@ impl<'arena, 'env> Context<'arena, 'env> {
         }
     }
 
+    /// Lookup the definition of a let binding that is being inlined, recording
+    /// if it would be captured by a more recently bound local.
+    fn get_inlined_local(&mut self, var: Index) -> Option<Term<'arena, ()>> {
+        let level = self.local_len().index_to_level(var)?;
+        let inlined_local = (self.inlined_locals.iter_mut()).find(|local| local.level == level)?;
+
+        // Names bound between the let binding and the use site
+        let mut bound_names = (env::indices().zip(self.local_names.iter().rev()))
+            .take_while(|(bound_var, _)| *bound_var < var)
+            .filter_map(|(_, name)| *name);
+        inlined_local.captured |= bound_names.any(|name| inlined_local.free_names.contains(&name));
+
+        Some(inlined_local.expr.clone())
+    }
+
+    /// Lookup the name bound to a subterm that is being hoisted.
+    fn get_hoisted_subterm(&self, term: &core::Term<'_>) -> Option<Symbol> {
+        let occurrence = term as *const core::Term<'_> as *const ();
+        (self.hoisted_subterms.iter())
+            .find(|(hoisted, _)| *hoisted == occurrence)
+            .map(|(_, name)| *name)
+    }
+
     /// Generate a fresh name that is not currently bound in the context
     fn gen_fresh_name(&mut self) -> Symbol {
         let mut counter = 0;
@@ -113,10 +211,27 @@ impl<'arena, 'env> Context<'arena, 'env> {
         }
     }
 
-    /// Replace `name` with a fresh name if it is `_` and occurs in `body`
+    /// Generate a fresh name based on `name` that is not currently bound in
+    /// the context
+    fn gen_fresh_name_from(&mut self, name: Symbol) -> Symbol {
+        let mut counter = 1;
+        loop {
+            let fresh_name = Symbol::intern(format!("{}{counter}", name.resolve()));
+            match self.is_bound(fresh_name) {
+                true => counter += 1,
+                false => return fresh_name,
+            }
+        }
+    }
+
+    /// Replace `name` with a fresh name if it is `_` and occurs in `body`, or
+    /// if it would capture an occurrence of a shadowed local in `body`
     fn freshen_name(&mut self, name: Option<Symbol>, body: &core::Term<'_>) -> Option<Symbol> {
         match name {
-            Some(name) => Some(name),
+            Some(name) => match self.local_names.elem_index(&Some(name)) {
+                Some(var) if body.binds_local(var.prev()) => Some(self.gen_fresh_name_from(name)),
+                _ => Some(name),
+            },
             None => body
                 .binds_local(Index::last())
                 .then(|| self.gen_fresh_name()),
@@ -142,15 +257,18 @@ impl<'arena, 'env> Context<'arena, 'env> {
                 r#type,
                 expr,
             } => {
-                let r#type = scope.to_scope(self.check_prec(Prec::Top, r#type));
-                let expr = scope.to_scope(self.check_prec(Prec::Let, expr));
+                let r#type = scope.to_scope(self.hoist_prec(Mode::Check, Prec::Top, r#type));
+                let expr = scope.to_scope(self.hoist_prec(Mode::Check, Prec::Let, expr));
 
                 Item::Def(ItemDef {
                     range: (),
+                    kind: ItemKind::Def,
+                    doc_comment: &[],
                     label: ((), *label),
                     params: &[],
                     r#type: Some(r#type),
                     expr,
+                    where_bindings: &[],
                 })
             }
         });
@@ -321,16 +439,90 @@ impl<'arena, 'env> Context<'arena, 'env> {
         }
     }
 
+    /// Distill a term, hoisting repeated closed subterms into let bindings if
+    /// [`Context::set_hoist_subterms`] is enabled.
+    fn hoist_prec(&mut self, mode: Mode, prec: Prec, term: &core::Term<'_>) -> Term<'arena, ()> {
+        // Subterms that only occur once are never worth hoisting
+        let min_uses = match self.hoist_subterms {
+            Some(min_uses) => Ord::max(min_uses, 2),
+            None => return self.term_prec(mode, prec, term),
+        };
+
+        let mut subterms = Vec::new();
+        let mut subterm_indices = FxHashMap::default();
+        let mut binder_names = Vec::new();
+        collect_subterms(
+            term,
+            &mut Vec::new(),
+            &mut subterms,
+            &mut subterm_indices,
+            &mut binder_names,
+        );
+
+        // Hoist subterms in the order they were first encountered, so that
+        // enclosing subterms are considered before the subterms they contain.
+        let mut hoisted = vec![false; subterms.len()];
+        let mut defs = Vec::new();
+        for (index, subterm) in subterms.iter().enumerate() {
+            // Occurrences within a hoisted subterm are only distilled once
+            let occurrences = (subterm.occurrences.iter())
+                .filter(|(_, enclosing)| !enclosing.iter().any(|index| hoisted[*index]))
+                .map(|(occurrence, _)| *occurrence)
+                .collect::<Vec<_>>();
+
+            if occurrences.len() >= min_uses {
+                // Avoid names that would be shadowed by a binder in the term
+                let name = (0..)
+                    .map(Symbol::get_alphabetic_name)
+                    .find(|name| !self.is_bound(*name) && !binder_names.contains(name))
+                    .unwrap();
+                // Synthesize the definition, as its type is not known
+                let expr = self.synth_prec(Prec::Let, subterm.term);
+
+                hoisted[index] = true;
+                defs.push((name, expr));
+                (self.hoisted_subterms).extend(occurrences.into_iter().map(|o| (o, name)));
+            }
+        }
+
+        if defs.is_empty() {
+            return self.term_prec(mode, prec, term);
+        }
+
+        let body = self.term_prec(mode, Prec::Top, term);
+        self.hoisted_subterms.clear();
+
+        let term = defs.into_iter().rev().fold(body, |body, (name, expr)| {
+            Term::Let(
+                (),
+                Pattern::Name((), name),
+                None,
+                self.scope.to_scope(expr),
+                self.scope.to_scope(body),
+            )
+        });
+        self.paren(prec > Prec::Let, term)
+    }
+
     fn term_prec(&mut self, mode: Mode, prec: Prec, term: &core::Term<'_>) -> Term<'arena, ()> {
+        if let Some(name) = self.get_hoisted_subterm(term) {
+            return Term::Name((), name);
+        }
+
         match (term, mode) {
             (core::Term::ItemVar(_, var), _) => match self.get_item_name(*var) {
                 Some(name) => Term::Name((), name),
                 None => panic!("Unbound item variable: {var:?}"),
             },
-            (core::Term::LocalVar(_, var), _) => match self.get_local_name(*var) {
-                Some(name) => Term::Name((), name),
-                None => panic!("Unbound local variable: {var:?}"),
-            },
+            (core::Term::LocalVar(_, var), _) => {
+                if let Some(expr) = self.get_inlined_local(*var) {
+                    return self.paren(prec > term_prec(&expr), expr);
+                }
+                match self.get_local_name(*var) {
+                    Some(name) => Term::Name((), name),
+                    None => panic!("Unbound local variable: {var:?}"),
+                }
+            }
             (core::Term::MetaVar(_, var), _) => match self.get_hole_name(*var) {
                 Some(name) => Term::Hole((), name),
                 None => Term::Placeholder(()),
@@ -365,11 +557,11 @@ impl<'arena, 'env> Context<'arena, 'env> {
 
                 self.paren(prec > Prec::App, Term::App((), head_expr, args.into()))
             }
-            (core::Term::Ann(_, expr, _), Mode::Check) => {
+            (core::Term::Ann(_, expr, _), Mode::Check) if !self.preserve_annotations => {
                 // Avoid adding extraneous type annotations!
                 self.check_prec(prec, expr)
             }
-            (core::Term::Ann(_, expr, r#type), Mode::Synth) => {
+            (core::Term::Ann(_, expr, r#type), _) => {
                 let expr = self.check_prec(Prec::Let, expr);
                 let r#type = self.check_prec(Prec::Top, r#type);
 
@@ -378,6 +570,38 @@ impl<'arena, 'env> Context<'arena, 'env> {
                     Term::Ann((), self.scope.to_scope(expr), self.scope.to_scope(r#type)),
                 )
             }
+            (core::Term::Let(_, _, _, expr, body), _)
+                if self.inline_lets && body.local_uses(Index::last()) == 1 =>
+            {
+                // Synthesize the definition, as its type annotation is lost
+                let inlined_expr = self.synth_prec(Prec::Top, expr);
+                let free_names = (env::indices().zip(self.local_names.iter().rev()))
+                    .filter_map(|(var, name)| name.filter(|_| expr.binds_local(var)))
+                    .collect();
+
+                let level = self.local_len().next_level();
+                self.inlined_locals.push(InlinedLocal {
+                    level,
+                    expr: inlined_expr,
+                    free_names,
+                    captured: false,
+                });
+                self.push_local(None);
+                let inlined_body = self.term_prec(mode, prec, body);
+                self.pop_local();
+                let inlined_local = self.inlined_locals.pop().unwrap();
+
+                match inlined_local.captured {
+                    false => inlined_body,
+                    // Fall back to distilling the let binding
+                    true => {
+                        let inline_lets = std::mem::replace(&mut self.inline_lets, false);
+                        let term = self.term_prec(mode, prec, term);
+                        self.inline_lets = inline_lets;
+                        term
+                    }
+                }
+            }
             (core::Term::Let(_, name, r#type, expr, body), _) => {
                 let r#type = self.term_prec(mode, Prec::Top, r#type);
                 let expr = self.term_prec(mode, Prec::Let, expr);
@@ -466,6 +690,22 @@ impl<'arena, 'env> Context<'arena, 'env> {
                     body_expr = next_body_expr;
                 }
 
+                if self.eta_contract {
+                    let plicities = params.iter().map(|(plicity, _)| *plicity);
+                    let (head_expr, num_contracted) = eta_contract(plicities, body_expr);
+
+                    // The contracted parameters remain bound in the local
+                    // environment, but are not referred to by the head.
+                    if num_contracted == params.len() {
+                        let head_expr = self.term_prec(mode, prec, head_expr);
+                        self.truncate_local(initial_local_len);
+                        return head_expr;
+                    }
+
+                    params.truncate(params.len() - num_contracted);
+                    body_expr = head_expr;
+                }
+
                 let body_expr = self.term_prec(mode, Prec::Let, body_expr);
                 self.truncate_local(initial_local_len);
 
@@ -696,7 +936,7 @@ impl<'arena, 'env> Context<'arena, 'env> {
 
     /// Distill a core term into a surface term, in a 'checkable' context.
     pub fn check(&mut self, core_term: &core::Term<'_>) -> Term<'arena, ()> {
-        self.check_prec(Prec::Top, core_term)
+        self.hoist_prec(Mode::Check, Prec::Top, core_term)
     }
 
     fn check_prec(&mut self, prec: Prec, core_term: &core::Term<'_>) -> Term<'arena, ()> {
@@ -705,7 +945,7 @@ impl<'arena, 'env> Context<'arena, 'env> {
 
     /// Distill a core term into a surface term, in a 'synthesizable' context.
     pub fn synth(&mut self, core_term: &core::Term<'_>) -> Term<'arena, ()> {
-        self.synth_prec(Prec::Top, core_term)
+        self.hoist_prec(Mode::Synth, Prec::Top, core_term)
     }
 
     fn synth_prec(&mut self, prec: Prec, core_term: &core::Term<'_>) -> Term<'arena, ()> {
@@ -713,6 +953,116 @@ impl<'arena, 'env> Context<'arena, 'env> {
     }
 }
 
+/// Collect the closed subterms of `term` that might be hoisted into let
+/// bindings, along with the names of the binders in `term`.
+///
+/// Subterms are indexed by the hash of their structure in `subterm_indices`,
+/// so that only subterms with the same hash need to be compared for equality.
+fn collect_subterms<'core>(
+    term: &'core core::Term<'core>,
+    enclosing: &mut Vec<usize>,
+    subterms: &mut Vec<Subterm<'core>>,
+    subterm_indices: &mut FxHashMap<u64, Vec<usize>>,
+    binder_names: &mut Vec<Symbol>,
+) {
+    // Only consider terms whose definitions can be synthesized
+    let is_candidate = matches!(
+        term,
+        core::Term::FunType(..)
+            | core::Term::FunApp(..)
+            | core::Term::RecordType(..)
+            | core::Term::RecordProj(..)
+            | core::Term::FormatRecord(..)
+            | core::Term::FormatCond(..)
+            | core::Term::FormatOverlap(..)
+    ) && !term.has_free_locals();
+
+    if is_candidate {
+        let mut hasher = FxHasher::default();
+        term.hash_structure(&mut hasher);
+        let indices = subterm_indices.entry(hasher.finish()).or_default();
+
+        let index = match (indices.iter()).find(|index| subterms[**index].term.is_equal(term)) {
+            Some(index) => *index,
+            None => {
+                indices.push(subterms.len());
+                subterms.push(Subterm {
+                    term,
+                    occurrences: Vec::new(),
+                });
+                subterms.len() - 1
+            }
+        };
+        let occurrence = term as *const core::Term<'_> as *const ();
+        subterms[index]
+            .occurrences
+            .push((occurrence, enclosing.clone()));
+        enclosing.push(index);
+    }
+
+    let mut collect =
+        |term| collect_subterms(term, enclosing, subterms, subterm_indices, binder_names);
+    match term {
+        core::Term::ItemVar(..)
+        | core::Term::LocalVar(..)
+        | core::Term::MetaVar(..)
+        | core::Term::InsertedMeta(..)
+        | core::Term::Universe(..)
+        | core::Term::Prim(..)
+        | core::Term::ConstLit(..) => {}
+        core::Term::Ann(_, expr, r#type) => {
+            collect(expr);
+            collect(r#type);
+        }
+        core::Term::Let(_, name, r#type, expr, body) => {
+            collect(r#type);
+            collect(expr);
+            collect(body);
+            binder_names.extend(*name);
+        }
+        core::Term::FunType(_, _, name, param_type, body_type) => {
+            collect(param_type);
+            collect(body_type);
+            binder_names.extend(*name);
+        }
+        core::Term::FunLit(_, _, name, body) => {
+            collect(body);
+            binder_names.extend(*name);
+        }
+        core::Term::FunApp(_, _, head_expr, arg_expr) => {
+            collect(head_expr);
+            collect(arg_expr);
+        }
+        core::Term::RecordType(_, labels, terms)
+        | core::Term::FormatRecord(_, labels, terms)
+        | core::Term::FormatOverlap(_, labels, terms) => {
+            terms.iter().for_each(&mut collect);
+            binder_names.extend(labels.iter());
+        }
+        core::Term::RecordLit(_, _, terms) | core::Term::ArrayLit(_, terms) => {
+            terms.iter().for_each(&mut collect);
+        }
+        core::Term::RecordProj(_, head_expr, _) => collect(head_expr),
+        core::Term::FormatCond(_, label, format, pred) => {
+            collect(format);
+            collect(pred);
+            binder_names.push(*label);
+        }
+        core::Term::ConstMatch(_, head_expr, branches, default_expr) => {
+            collect(head_expr);
+            branches.iter().for_each(|(_, term)| collect(term));
+            if let Some((name, term)) = default_expr {
+                collect(term);
+                binder_names.extend(*name);
+            }
+        }
+    }
+
+    if is_candidate {
+        enclosing.pop();
+    }
+}
+
 fn name_to_pattern(name: Option<Symbol>) -> Pattern<()> {
     match name {
         Some(name) => Pattern::Name((), name),
@@ -720,6 +1070,36 @@ fn name_to_pattern(name: Option<Symbol>) -> Pattern<()> {
     }
 }
 
+/// Eta-contract a function body with respect to its parameters, returning the
+/// contracted body and the number of trailing parameters that were removed.
+///
+/// For example, the body of `fun x y => f x y` is contracted to `f`, removing
+/// two parameters, and the body of `fun x y => f y` is contracted to `f`,
+/// removing one parameter.
+fn eta_contract<'a>(
+    plicities: impl DoubleEndedIterator<Item = Plicity>,
+    mut body_expr: &'a core::Term<'a>,
+) -> (&'a core::Term<'a>, usize) {
+    let mut num_contracted = 0;
+
+    for (var, plicity) in Iterator::zip(env::indices(), plicities.rev()) {
+        match body_expr {
+            core::Term::FunApp(_, arg_plicity, head_expr, core::Term::LocalVar(_, arg_var))
+                if *arg_plicity == plicity
+                    && *arg_var == var
+                    && !(env::indices().take(num_contracted + 1))
+                        .any(|var| head_expr.binds_local(var)) =>
+            {
+                body_expr = head_expr;
+                num_contracted += 1;
+            }
+            _ => break,
+        }
+    }
+
+    (body_expr, num_contracted)
+}
+
 fn match_if_then_else<'arena>(
     branches: &'arena [(Const, core::Term<'arena>)],
     default_branch: Option<(Option<Symbol>, &'arena core::Term<'arena>)>,
@@ -745,6 +1125,37 @@ fn is_tuple_type(labels: &[Symbol], types: &[core::Term<'_>]) -> bool {
         })
 }
 
+/// The precedence of a distilled term, used to decide if an inlined term
+/// needs to be wrapped in parentheses.
+fn term_prec(term: &Term<'_, ()>) -> Prec {
+    match term {
+        Term::Ann(..) => Prec::Top,
+        Term::Let(..) | Term::If(..) => Prec::Let,
+        Term::Arrow(..) | Term::FunType(..) | Term::FunLiteral(..) => Prec::Fun,
+        Term::BinOp(_, _, op, _) => op.precedence(),
+        Term::App(..) => Prec::App,
+        Term::Proj(..) => Prec::Proj,
+        Term::Paren(..)
+        | Term::Name(..)
+        | Term::Hole(..)
+        | Term::Placeholder(..)
+        | Term::Match(..)
+        | Term::Universe(..)
+        | Term::RecordType(..)
+        | Term::RecordLiteral(..)
+        | Term::Tuple(..)
+        | Term::ArrayLiteral(..)
+        | Term::StringLiteral(..)
+        | Term::ByteStringLiteral(..)
+        | Term::NumberLiteral(..)
+        | Term::BooleanLiteral(..)
+        | Term::FormatRecord(..)
+        | Term::FormatOverlap(..)
+        | Term::FormatCond(..)
+        | Term::ReportedError(..) => Prec::Atomic,
+    }
+}
+
 fn prim_to_bin_op(prim: &core::Prim) -> Option<BinOp<()>> {
     use crate::core::Prim::*;
 
@@ -762,6 +1173,7 @@ fn prim_to_bin_op(prim: &core::Prim) -> Option<BinOp<()>> {
         U8Lte | U16Lte | U32Lte | U64Lte | S8Lte | S16Lte | S32Lte | S64Lte => Some(BinOp::Lte(())),
         U8Gt | U16Gt | U32Gt | U64Gt | S8Gt | S16Gt | S32Gt | S64Gt => Some(BinOp::Gt(())),
         U8Gte | U16Gte | U32Gte | U64Gte | S8Gte | S16Gte | S32Gte | S64Gte => Some(BinOp::Gte(())),
+        FormatThen => Some(BinOp::Then(())),
 
         _ => None,
     }
@@ -785,12 +1197,211 @@ impl<Range> BinOp<Range> {
     /// (lhs, op, rhs)
     fn precedence_impl(&self) -> (Prec, Prec, Prec) {
         match self {
-            BinOp::Eq(_) | BinOp::Neq(_) => (Prec::Cmp, Prec::Eq, Prec::Eq),
+            BinOp::Then(_) => (Prec::Eq, Prec::Then, Prec::Then),
+            BinOp::Eq(_) | BinOp::Neq(_) => (Prec::Cmp, Prec::Eq, Prec::Cmp),
             BinOp::Lt(_) | BinOp::Lte(_) | BinOp::Gt(_) | BinOp::Gte(_) => {
-                (Prec::Add, Prec::Cmp, Prec::Cmp)
+                (Prec::Add, Prec::Cmp, Prec::Add)
             }
-            BinOp::Add(_) | BinOp::Sub(_) => (Prec::Mul, Prec::Add, Prec::Add),
-            BinOp::Mul(_) | BinOp::Div(_) => (Prec::App, Prec::Mul, Prec::Mul),
+            BinOp::Add(_) | BinOp::Sub(_) => (Prec::Add, Prec::Add, Prec::Mul),
+            BinOp::Mul(_) | BinOp::Div(_) => (Prec::Mul, Prec::Mul, Prec::App),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::surface::pretty;
+
+    fn distill(
+        local_names: &[&str],
+        term: &core::Term<'_>,
+        configure: impl FnOnce(&mut Context<'_, '_>),
+    ) -> String {
+        let scope = Scope::new();
+        let item_names = UniqueEnv::new();
+        let meta_sources = UniqueEnv::new();
+        let mut local_env = UniqueEnv::new();
+        for name in local_names {
+            local_env.push(Some(Symbol::intern(name)));
+        }
+
+        let mut context = Context::new(&scope, &item_names, local_env, &meta_sources);
+        configure(&mut context);
+        let term = context.synth(term);
+
+        pretty::Context::new(&scope)
+            .term(&term)
+            .pretty(usize::MAX)
+            .to_string()
+    }
+
+    #[test]
+    fn inline_lets() {
+        // let x : Type = Type; x -> Type
+        let term = core::Term::Let(
+            Span::Empty,
+            Some(Symbol::intern("x")),
+            &core::Term::Universe(Span::Empty),
+            &core::Term::Universe(Span::Empty),
+            &core::Term::FunType(
+                Span::Empty,
+                Plicity::Explicit,
+                None,
+                &core::Term::LocalVar(Span::Empty, Index::last()),
+                &core::Term::Universe(Span::Empty),
+            ),
+        );
+
+        assert_eq!(
+            distill(&[], &term, |_| {}),
+            "let x : Type = Type;\nx -> Type"
+        );
+        assert_eq!(
+            distill(&[], &term, |context| context.set_inline_lets(true)),
+            "Type -> Type"
+        );
+    }
+
+    #[test]
+    fn inline_lets_multiple_uses() {
+        // let x : Type = Type; x -> x
+        let term = core::Term::Let(
+            Span::Empty,
+            Some(Symbol::intern("x")),
+            &core::Term::Universe(Span::Empty),
+            &core::Term::Universe(Span::Empty),
+            &core::Term::FunType(
+                Span::Empty,
+                Plicity::Explicit,
+                None,
+                &core::Term::LocalVar(Span::Empty, Index::last()),
+                &core::Term::LocalVar(Span::Empty, Index::last().prev()),
+            ),
+        );
+
+        assert_eq!(
+            distill(&[], &term, |context| context.set_inline_lets(true)),
+            "let x : Type = Type;\nx -> x"
+        );
+    }
+
+    #[test]
+    fn inline_lets_captured() {
+        // let x : Type = y; fun (y : Type) -> y -> x
+        let term = core::Term::Let(
+            Span::Empty,
+            Some(Symbol::intern("x")),
+            &core::Term::Universe(Span::Empty),
+            &core::Term::LocalVar(Span::Empty, Index::last()),
+            &core::Term::FunType(
+                Span::Empty,
+                Plicity::Explicit,
+                Some(Symbol::intern("y")),
+                &core::Term::Universe(Span::Empty),
+                &core::Term::FunType(
+                    Span::Empty,
+                    Plicity::Explicit,
+                    None,
+                    &core::Term::LocalVar(Span::Empty, Index::last()),
+                    &core::Term::LocalVar(Span::Empty, Index::last().prev().prev()),
+                ),
+            ),
+        );
+
+        assert_eq!(
+            distill(&["y"], &term, |context| { context.set_inline_lets(true) }),
+            "let x : Type = y;\nfun (y : Type) -> y -> x",
+        );
+    }
+
+    #[test]
+    fn hoist_subterms() {
+        // (Type -> Type) -> (Type -> Type) -> (Type -> Type)
+        let arrow = core::Term::FunType(
+            Span::Empty,
+            Plicity::Explicit,
+            None,
+            &core::Term::Universe(Span::Empty),
+            &core::Term::Universe(Span::Empty),
+        );
+        let term = core::Term::FunType(
+            Span::Empty,
+            Plicity::Explicit,
+            None,
+            &arrow,
+            &core::Term::FunType(Span::Empty, Plicity::Explicit, None, &arrow, &arrow),
+        );
+
+        assert_eq!(
+            distill(&[], &term, |_| {}),
+            "(Type -> Type) -> (Type -> Type) -> Type -> Type"
+        );
+        assert_eq!(
+            distill(&[], &term, |context| context.set_hoist_subterms(Some(3))),
+            "let a = Type -> Type;\na -> a -> a"
+        );
+        assert_eq!(
+            distill(&[], &term, |context| context.set_hoist_subterms(Some(4))),
+            "(Type -> Type) -> (Type -> Type) -> Type -> Type"
+        );
+    }
+
+    #[test]
+    fn hoist_subterms_shadowed() {
+        // fun (a : Type) -> (Type -> Type) -> (Type -> Type) -> a
+        let arrow = core::Term::FunType(
+            Span::Empty,
+            Plicity::Explicit,
+            None,
+            &core::Term::Universe(Span::Empty),
+            &core::Term::Universe(Span::Empty),
+        );
+        let term = core::Term::FunType(
+            Span::Empty,
+            Plicity::Explicit,
+            Some(Symbol::intern("a")),
+            &core::Term::Universe(Span::Empty),
+            &core::Term::FunType(
+                Span::Empty,
+                Plicity::Explicit,
+                None,
+                &arrow,
+                &core::Term::FunType(
+                    Span::Empty,
+                    Plicity::Explicit,
+                    None,
+                    &arrow,
+                    &core::Term::LocalVar(Span::Empty, Index::last().prev().prev()),
+                ),
+            ),
+        );
+
+        assert_eq!(
+            distill(&[], &term, |context| context.set_hoist_subterms(Some(2))),
+            "let b = Type -> Type;\nfun (a : Type) -> b -> b -> a"
+        );
+    }
+
+    #[test]
+    fn preserve_annotations() {
+        // (Type : Type) -> Type
+        let term = core::Term::FunType(
+            Span::Empty,
+            Plicity::Explicit,
+            None,
+            &core::Term::Ann(
+                Span::Empty,
+                &core::Term::Universe(Span::Empty),
+                &core::Term::Universe(Span::Empty),
+            ),
+            &core::Term::Universe(Span::Empty),
+        );
+
+        assert_eq!(distill(&[], &term, |_| {}), "Type -> Type");
+        assert_eq!(
+            distill(&[], &term, |context| context.set_preserve_annotations(true)),
+            "(Type : Type) -> Type",
+        );
+    }
+}
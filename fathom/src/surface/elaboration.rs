@@ -219,6 +219,13 @@ struct MetaEnv<'arena> {
     /// [inserted][Context::push_unsolved_term], then will be set to [`Some`]
     /// if a solution is found during [`unification`].
     exprs: UniqueEnv<Option<ArcValue<'arena>>>,
+    /// Pretty printed types of hole expressions that were checked against an
+    /// expected type, used when reporting [unsolved
+    /// metavariables][Message::UnsolvedMetaVar].
+    hole_types: UniqueEnv<Option<String>>,
+    /// Metavariables in the order that they were solved, used to undo
+    /// solutions when [restoring a snapshot][Context::restore].
+    solved: Vec<Level>,
 }
 
 impl<'arena> MetaEnv<'arena> {
@@ -228,17 +235,25 @@ impl<'arena> MetaEnv<'arena> {
             sources: UniqueEnv::new(),
             types: UniqueEnv::new(),
             exprs: UniqueEnv::new(),
+            hole_types: UniqueEnv::new(),
+            solved: Vec::new(),
         }
     }
 
     /// Push an unsolved metavariable onto the context.
-    fn push(&mut self, source: MetaSource, r#type: ArcValue<'arena>) -> Level {
+    fn push(
+        &mut self,
+        source: MetaSource,
+        r#type: ArcValue<'arena>,
+        hole_type: Option<String>,
+    ) -> Level {
         // TODO: check that hole name is not already in use
         let var = self.exprs.len().next_level();
 
         self.sources.push(source);
         self.types.push(r#type);
         self.exprs.push(None);
+        self.hole_types.push(hole_type);
 
         var
     }
@@ -271,6 +286,29 @@ pub struct Context<'arena> {
     renaming: unification::PartialRenaming,
     /// Diagnostic messages encountered during elaboration.
     messages: Vec<Message>,
+    /// Trace unification problems and their solutions.
+    dump_unification: bool,
+    /// Trace the checking and synthesis of each subterm.
+    explain_type: bool,
+    /// Lines of the elaboration trace that are yet to be handled, along with
+    /// their depth. Lines are reserved before they are known, so that terms
+    /// are traced before their subterms.
+    trace_lines: Vec<(usize, Option<String>)>,
+    /// The current depth of the elaboration trace.
+    trace_depth: usize,
+}
+
+/// A checkpoint of the mutable state of an elaboration context, allowing
+/// speculative elaboration to be rolled back with [`Context::restore`].
+pub struct Snapshot {
+    /// Length of the meta environment.
+    meta_len: EnvLen,
+    /// Number of metavariables that had been solved.
+    solved_len: usize,
+    /// Length of the local environment.
+    local_len: EnvLen,
+    /// Number of diagnostic messages.
+    messages_len: usize,
 }
 
 fn suggest_name(name: Symbol, candidates: impl Iterator<Item = Symbol>) -> Option<Symbol> {
@@ -302,13 +340,56 @@ impl<'arena> Context<'arena> {
             local_env: LocalEnv::new(),
             renaming: unification::PartialRenaming::new(),
             messages: Vec::new(),
+            dump_unification: false,
+            explain_type: false,
+            trace_lines: Vec::new(),
+            trace_depth: 0,
         }
     }
 
+    /// Set to true if unification problems and metavariable solutions should
+    /// be added to the elaboration trace.
+    pub fn set_dump_unification(&mut self, dump_unification: bool) {
+        self.dump_unification = dump_unification;
+    }
+
+    /// Set to true if the checking and synthesis of each subterm, along with
+    /// the resulting unification problems, should be added to the elaboration
+    /// trace as a tree following the structure of the term.
+    pub fn set_explain_type(&mut self, explain_type: bool) {
+        self.explain_type = explain_type;
+    }
+
     pub fn finish(self) -> ItemEnv<'arena> {
         self.item_env
     }
 
+    /// Take a snapshot of the metavariables, local bindings and messages of
+    /// the context.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            meta_len: self.meta_env.exprs.len(),
+            solved_len: self.meta_env.solved.len(),
+            local_len: self.local_env.len(),
+            messages_len: self.messages.len(),
+        }
+    }
+
+    /// Restore the context to a previous snapshot, discarding any
+    /// metavariables, solutions, local bindings and messages that were added
+    /// since it was taken.
+    pub fn restore(&mut self, snapshot: Snapshot) {
+        for var in self.meta_env.solved.drain(snapshot.solved_len..) {
+            self.meta_env.exprs.set_level(var, None);
+        }
+        self.meta_env.sources.truncate(snapshot.meta_len);
+        self.meta_env.types.truncate(snapshot.meta_len);
+        self.meta_env.exprs.truncate(snapshot.meta_len);
+        self.meta_env.hole_types.truncate(snapshot.meta_len);
+        self.local_env.truncate(snapshot.local_len);
+        self.messages.truncate(snapshot.messages_len);
+    }
+
     fn file_range(&self, byte_range: ByteRange) -> FileRange {
         FileRange::new(self.file_id, byte_range)
     }
@@ -338,7 +419,23 @@ impl<'arena> Context<'arena> {
     ) -> core::Term<'arena> {
         core::Term::InsertedMeta(
             source.range().into(),
-            self.meta_env.push(source, r#type),
+            self.meta_env.push(source, r#type, None),
+            (self.scope).to_scope_from_iter(self.local_env.infos.iter().copied()),
+        )
+    }
+
+    /// Push an unsolved hole expression of a known type onto the context,
+    /// remembering the type so that it can be reported if the hole is not
+    /// solved.
+    fn push_unsolved_hole(
+        &mut self,
+        source: MetaSource,
+        r#type: ArcValue<'arena>,
+    ) -> core::Term<'arena> {
+        let hole_type = self.pretty_value(&r#type);
+        core::Term::InsertedMeta(
+            source.range().into(),
+            self.meta_env.push(source, r#type, Some(hole_type)),
             (self.scope).to_scope_from_iter(self.local_env.infos.iter().copied()),
         )
     }
@@ -354,13 +451,45 @@ impl<'arena> Context<'arena> {
         self.messages.push(message);
     }
 
+    /// Handle the lines of the elaboration trace that have been recorded so
+    /// far, indented according to their depth in the trace.
+    pub fn handle_trace(&mut self, on_trace: &mut dyn FnMut(String)) {
+        for (depth, line) in self.trace_lines.drain(..) {
+            // Lines are left unfinished if elaboration was interrupted
+            if let Some(line) = line {
+                on_trace(format!("{:indent$}{line}", "", indent = depth * 2));
+            }
+        }
+    }
+
+    /// Reserve a line in the elaboration trace, to be filled in with
+    /// [`Context::finish_trace_line`]. Lines traced in the meantime are
+    /// nested under the reserved line.
+    fn start_trace_line(&mut self) -> usize {
+        self.trace_lines.push((self.trace_depth, None));
+        self.trace_depth += 1;
+        self.trace_lines.len() - 1
+    }
+
+    /// Fill in a line that was reserved with [`Context::start_trace_line`].
+    fn finish_trace_line(&mut self, index: usize, line: String) {
+        self.trace_depth -= 1;
+        self.trace_lines[index].1 = Some(line);
+    }
+
+    /// Add a line to the elaboration trace.
+    fn trace_line(&mut self, line: String) {
+        self.trace_lines.push((self.trace_depth, Some(line)));
+    }
+
     pub fn handle_messages(&mut self, on_message: &mut dyn FnMut(Message)) {
         for message in self.messages.drain(..) {
             on_message(message);
         }
 
         let meta_env = &self.meta_env;
-        for (expr, source) in Iterator::zip(meta_env.exprs.iter(), meta_env.sources.iter()) {
+        let metas = Iterator::zip(meta_env.exprs.iter(), meta_env.sources.iter());
+        for ((expr, source), hole_type) in Iterator::zip(metas, meta_env.hole_types.iter()) {
             match (expr, *source) {
                 // Avoid producing messages for some unsolved metavariable sources:
                 // Should have an unsolved hole expression
@@ -371,7 +500,10 @@ impl<'arena> Context<'arena> {
                 (None, MetaSource::ReportedErrorType(_)) => {}
 
                 // For other sources, report an unsolved problem message
-                (None, source) => on_message(Message::UnsolvedMetaVar { source }),
+                (None, source) => on_message(Message::UnsolvedMetaVar {
+                    source,
+                    r#type: hole_type.clone(),
+                }),
                 // Yield messages of solved named holes
                 (Some(expr), MetaSource::HoleExpr(range, name)) => {
                     let expr = self.pretty_value(expr);
@@ -403,9 +535,91 @@ impl<'arena> Context<'arena> {
             &self.item_env.exprs,
             self.local_env.len(),
             &mut self.meta_env.exprs,
+            &mut self.meta_env.solved,
         )
     }
 
+    /// Unify two values, tracing the problem and any metavariable solutions
+    /// if [`Context::set_dump_unification`] or [`Context::set_explain_type`]
+    /// is enabled.
+    fn unify(
+        &mut self,
+        value0: &ArcValue<'arena>,
+        value1: &ArcValue<'arena>,
+    ) -> Result<(), unification::Error> {
+        if !(self.dump_unification || self.explain_type) {
+            return self.unification_context().unify(value0, value1);
+        }
+
+        let value0_doc = self.pretty_value(value0);
+        let value1_doc = self.pretty_value(value1);
+        let unsolved_vars = Iterator::zip(env::levels(), self.meta_env.exprs.iter())
+            .filter_map(|(var, expr)| expr.is_none().then_some(var))
+            .collect::<Vec<_>>();
+
+        let line_index = self.start_trace_line();
+        let result = self.unification_context().unify(value0, value1);
+
+        for var in unsolved_vars {
+            if let Some(Some(expr)) = self.meta_env.exprs.get_level(var) {
+                let expr_doc = self.pretty_value(expr);
+                self.trace_line(format!("solved: ?{var} := {expr_doc}"));
+            }
+        }
+        match &result {
+            Ok(()) => self.trace_line("ok".to_owned()),
+            Err(error) => self.trace_line(format!("failed: {error}")),
+        }
+        self.finish_trace_line(line_index, format!("unify: {value0_doc} =? {value1_doc}"));
+
+        result
+    }
+
+    /// Check that a surface term conforms to the given type.
+    ///
+    /// Returns the elaborated term in the core language, which is traced if
+    /// [`Context::set_explain_type`] is enabled.
+    fn check(
+        &mut self,
+        surface_term: &Term<'_, ByteRange>,
+        expected_type: &ArcValue<'arena>,
+    ) -> core::Term<'arena> {
+        if !self.explain_type {
+            return self.check_impl(surface_term, expected_type);
+        }
+
+        let line_index = self.start_trace_line();
+        let term = self.check_impl(surface_term, expected_type);
+
+        let term_doc = self.pretty_term(&term);
+        let type_doc = self.pretty_value(expected_type);
+        self.finish_trace_line(line_index, format!("check: {term_doc} : {type_doc}"));
+
+        term
+    }
+
+    /// Synthesize the type of the given surface term.
+    ///
+    /// Returns the elaborated term in the core language and its type, which
+    /// are traced if [`Context::set_explain_type`] is enabled.
+    fn synth(
+        &mut self,
+        surface_term: &Term<'_, ByteRange>,
+    ) -> (core::Term<'arena>, ArcValue<'arena>) {
+        if !self.explain_type {
+            return self.synth_impl(surface_term);
+        }
+
+        let line_index = self.start_trace_line();
+        let (term, r#type) = self.synth_impl(surface_term);
+
+        let term_doc = self.pretty_term(&term);
+        let type_doc = self.pretty_value(&r#type);
+        self.finish_trace_line(line_index, format!("synth: {term_doc} : {type_doc}"));
+
+        (term, r#type)
+    }
+
     pub fn distillation_context<'out_arena>(
         &self,
         scope: &'out_arena Scope<'out_arena>,
@@ -418,6 +632,15 @@ impl<'arena> Context<'arena> {
         )
     }
 
+    fn pretty_term(&self, term: &core::Term<'_>) -> String {
+        let surface_term = self.distillation_context(self.scope).check(term);
+
+        pretty::Context::new(self.scope)
+            .term(&surface_term)
+            .pretty(usize::MAX)
+            .to_string()
+    }
+
     fn pretty_value(&self, value: &ArcValue<'_>) -> String {
         let term = self.quote_env().unfolding_metas().quote(self.scope, value);
         let surface_term = self.distillation_context(self.scope).check(&term);
@@ -515,6 +738,45 @@ impl<'arena> Context<'arena> {
         num.map(|num| make(num, UIntStyle::Ascii))
     }
 
+    /// Parse the hexadecimal digits of a byte string literal into the source
+    /// ranges and values of each byte.
+    fn parse_byte_string(
+        &mut self,
+        range: ByteRange,
+        symbol: Symbol,
+    ) -> Option<Vec<(ByteRange, u8)>> {
+        let source = symbol.resolve();
+        let digits_start = range.start() + 3; // skip `0x"`
+
+        if let Some((offset, ch)) = (source.char_indices()).find(|(_, ch)| !ch.is_ascii_hexdigit())
+        {
+            let ch_start = digits_start + offset as BytePos;
+            let ch_end = ch_start + ch.len_utf8() as BytePos;
+
+            self.push_message(Message::InvalidByteStringDigit {
+                invalid_range: self.file_range(ByteRange::new(ch_start, ch_end)),
+            });
+            return None;
+        }
+
+        if source.len() % 2 != 0 {
+            self.push_message(Message::OddLengthByteStringLiteral {
+                range: self.file_range(range),
+                found_len: source.len(),
+            });
+            return None;
+        }
+
+        let bytes = (0..source.len()).step_by(2).map(|offset| {
+            let byte_start = digits_start + offset as BytePos;
+            // Digits were checked above, so this should not fail
+            let byte = u8::from_str_radix(&source[offset..(offset + 2)], 16).unwrap();
+            (ByteRange::new(byte_start, byte_start + 2), byte)
+        });
+
+        Some(bytes.collect())
+    }
+
     /// Parse a source string into a number.
     fn parse_number<T: FromStr>(
         &mut self,
@@ -548,6 +810,13 @@ impl<'arena> Context<'arena> {
     ) -> Option<Const> {
         // TODO: Custom parsing and improved errors
         let s = symbol.resolve();
+        if s.starts_with('-') {
+            self.push_message(Message::InvalidNumericLiteral {
+                range: self.file_range(range),
+                message: "negative number in unsigned type".to_owned(),
+            });
+            return None;
+        }
         let (s, radix, style) = if let Some(s) = s.strip_prefix("0x") {
             (s, 16, UIntStyle::Hexadecimal)
         } else if let Some(s) = s.strip_prefix("0b") {
@@ -598,7 +867,7 @@ impl<'arena> Context<'arena> {
             }
 
             // Otherwise, unify the types
-            (_, _) => match self.unification_context().unify(&from, &to) {
+            (_, _) => match self.unify(&from, &to) {
                 Ok(()) => expr,
                 Err(error) => {
                     let range = match span {
@@ -632,12 +901,15 @@ impl<'arena> Context<'arena> {
         let elab_order = order::elaboration_order(self, surface_module);
         let mut items = Vec::with_capacity(surface_module.items.len());
         self.item_env.reserve(surface_module.items.len());
+        // Scope for storing desugared `where` clauses
+        let where_scope = Scope::new();
 
         for item in elab_order.iter().copied().map(|i| &surface_module.items[i]) {
             match item {
                 Item::Def(item) => {
+                    let item_expr = item.desugar_where(&where_scope);
                     let (expr, r#type) =
-                        self.synth_fun_lit(item.range, item.params, item.expr, item.r#type);
+                        self.synth_fun_lit(item.range, item.params, item_expr, item.r#type);
                     let expr_value = self.eval_env().eval(&expr);
                     let type_value = self.eval_env().eval(&r#type);
 
@@ -853,7 +1125,7 @@ impl<'arena> Context<'arena> {
                 let r#type = self.check(r#type, &self.universe.clone());
                 let r#type = self.eval_env().eval(&r#type);
 
-                match self.unification_context().unify(&r#type, expected_type) {
+                match self.unify(&r#type, expected_type) {
                     Ok(()) => self.check_pattern(pattern, &r#type),
                     Err(error) => {
                         self.push_message(Message::FailedToUnify {
@@ -957,10 +1229,7 @@ impl<'arena> Context<'arena> {
         }))
     }
 
-    /// Check that a surface term conforms to the given type.
-    ///
-    /// Returns the elaborated term in the core language.
-    fn check(
+    fn check_impl(
         &mut self,
         surface_term: &Term<'_, ByteRange>,
         expected_type: &ArcValue<'arena>,
@@ -969,7 +1238,11 @@ impl<'arena> Context<'arena> {
         let expected_type = self.elim_env().force(expected_type);
 
         match (surface_term, expected_type.as_ref()) {
-            (Term::Paren(_, term), _) => self.check(term, &expected_type),
+            (Term::Paren(_, term), _) => self.check_impl(term, &expected_type),
+            (Term::Hole(_, name), _) => {
+                let source = MetaSource::HoleExpr(file_range, *name);
+                self.push_unsolved_hole(source, expected_type)
+            }
             (Term::Let(_, def_pattern, def_type, def_expr, body_expr), _) => {
                 let (def_pattern, def_type, def_type_value) =
                     self.synth_ann_pattern(def_pattern, *def_type);
@@ -1156,6 +1429,13 @@ impl<'arena> Context<'arena> {
                     Some((Prim::ReportedError, _)) => {
                         return core::Term::Prim(file_range.into(), Prim::ReportedError)
                     }
+                    // Synthesize the type of the array literal if the expected
+                    // type is not yet known, eg. in unannotated let bindings.
+                    None if matches!(expected_type.as_ref(), Value::Stuck(Head::MetaVar(_), _)) => {
+                        let surface_range = surface_term.range();
+                        let (synth_term, synth_type) = self.synth(surface_term);
+                        return self.coerce(surface_range, synth_term, &synth_type, &expected_type);
+                    }
                     _ => {
                         self.push_message(Message::ArrayLiteralNotSupported {
                             range: file_range,
@@ -1226,6 +1506,51 @@ impl<'arena> Context<'arena> {
                     None => core::Term::Prim(file_range.into(), Prim::ReportedError),
                 }
             }
+            (Term::ByteStringLiteral(range, lit), _) => {
+                use crate::core::semantics::Elim::FunApp as App;
+
+                // The expected array type, with its elements replaced by bytes
+                let u8_type = Spanned::empty(Arc::new(Value::prim(Prim::U8Type, [])));
+                let bytes_type = match expected_type.match_prim_spine() {
+                    Some((Prim::ArrayType, [App(_, _)])) => Value::prim(Prim::ArrayType, [u8_type]),
+                    Some((
+                        prim @ (Prim::Array8Type
+                        | Prim::Array16Type
+                        | Prim::Array32Type
+                        | Prim::Array64Type),
+                        [App(_, len), App(_, _)],
+                    )) => Value::prim(prim, [len.clone(), u8_type]),
+                    _ => {
+                        let surface_range = surface_term.range();
+                        let (synth_term, synth_type) = self.synth(surface_term);
+                        return self.coerce(surface_range, synth_term, &synth_type, &expected_type);
+                    }
+                };
+
+                let bytes_type = Spanned::empty(Arc::new(bytes_type));
+                if let Err(error) = self.unify(&bytes_type, &expected_type) {
+                    self.push_message(Message::FailedToUnify {
+                        range: file_range,
+                        found: self.pretty_value(&bytes_type),
+                        expected: self.pretty_value(&expected_type),
+                        error,
+                    });
+                    return core::Term::Prim(file_range.into(), Prim::ReportedError);
+                }
+
+                let bytes = match self.parse_byte_string(*range, *lit) {
+                    Some(bytes) => bytes,
+                    None => return core::Term::Prim(file_range.into(), Prim::ReportedError),
+                };
+
+                // Check the bytes as an array literal of hexadecimal numbers
+                let elem_exprs =
+                    self.scope
+                        .to_scope_from_iter(bytes.iter().map(|(range, byte)| {
+                            Term::NumberLiteral(*range, Symbol::intern(format!("0x{byte:02X}")))
+                        }));
+                self.check(&Term::ArrayLiteral(*range, elem_exprs), &expected_type)
+            }
             (Term::NumberLiteral(range, lit), _) => {
                 let constant = match expected_type.match_prim_spine() {
                     Some((Prim::U8Type, [])) => self.parse_number_radix(*range, *lit, Const::U8),
@@ -1305,16 +1630,13 @@ impl<'arena> Context<'arena> {
         }
     }
 
-    /// Synthesize the type of the given surface term.
-    ///
-    /// Returns the elaborated term in the core language and its type.
-    fn synth(
+    fn synth_impl(
         &mut self,
         surface_term: &Term<'_, ByteRange>,
     ) -> (core::Term<'arena>, ArcValue<'arena>) {
         let file_range = self.file_range(surface_term.range());
         match surface_term {
-            Term::Paren(_, term) => self.synth(term),
+            Term::Paren(_, term) => self.synth_impl(term),
             Term::Name(range, name) => {
                 if let Some((term, r#type)) = self.get_local_name(*name) {
                     return (
@@ -1474,9 +1796,10 @@ impl<'arena> Context<'arena> {
             }
             Term::App(range, head_expr, args) => {
                 let mut head_range = head_expr.range();
+                let head_args = applied_args(head_expr);
                 let (mut head_expr, mut head_type) = self.synth(head_expr);
 
-                for arg in *args {
+                for (arg_index, arg) in args.iter().enumerate() {
                     head_type = self.elim_env().force(&head_type);
 
                     match arg.plicity {
@@ -1512,10 +1835,16 @@ impl<'arena> Context<'arena> {
                         _ => {
                             // NOTE: We could try to infer that this is a function type,
                             // but this takes more work to prevent cascading type errors
+                            let last_arg = &args[args.len() - 1];
                             self.push_message(Message::UnexpectedArgument {
                                 head_range: self.file_range(head_range),
                                 head_type: self.pretty_value(&head_type),
-                                arg_range: self.file_range(arg.term.range()),
+                                arg_range: self.file_range(ByteRange::merge(
+                                    arg.term.range(),
+                                    last_arg.term.range(),
+                                )),
+                                expected_args: head_args + arg_index,
+                                supplied_args: head_args + args.len(),
                             });
                             return self.synth_reported_error(*range);
                         }
@@ -1662,9 +1991,55 @@ impl<'arena> Context<'arena> {
 
                 (head_expr, head_type)
             }
-            Term::ArrayLiteral(range, _) => {
-                self.push_message(Message::AmbiguousArrayLiteral { range: file_range });
-                self.synth_reported_error(*range)
+            Term::ArrayLiteral(range, elem_exprs) => {
+                let (first_expr, next_exprs) = match elem_exprs.split_first() {
+                    Some(elem_exprs) => elem_exprs,
+                    None => {
+                        self.push_message(Message::AmbiguousArrayLiteral { range: file_range });
+                        return self.synth_reported_error(*range);
+                    }
+                };
+
+                // Infer the element type from the first element, checking the
+                // remaining elements against it.
+                let (first_expr, elem_type) = self.synth(first_expr);
+                let elem_exprs =
+                    self.scope
+                        .to_scope_from_iter(std::iter::once(first_expr).chain(
+                            (next_exprs.iter()).map(|elem_expr| self.check(elem_expr, &elem_type)),
+                        ));
+
+                let len = Const::U64(elem_exprs.len() as u64, UIntStyle::Decimal);
+                let len = Spanned::empty(Arc::new(Value::ConstLit(len)));
+                let array_type = Value::prim(Prim::Array64Type, [len, elem_type]);
+
+                (
+                    core::Term::ArrayLit(file_range.into(), elem_exprs),
+                    Spanned::empty(Arc::new(array_type)),
+                )
+            }
+            Term::ByteStringLiteral(range, lit) => {
+                let bytes = match self.parse_byte_string(*range, *lit) {
+                    Some(bytes) => bytes,
+                    None => return self.synth_reported_error(*range),
+                };
+
+                let elem_exprs =
+                    self.scope
+                        .to_scope_from_iter(bytes.iter().map(|(range, byte)| {
+                            let constant = Const::U8(*byte, UIntStyle::Hexadecimal);
+                            core::Term::ConstLit(self.file_range(*range).into(), constant)
+                        }));
+
+                let len = Const::U64(elem_exprs.len() as u64, UIntStyle::Decimal);
+                let len = Spanned::empty(Arc::new(Value::ConstLit(len)));
+                let elem_type = Spanned::empty(Arc::new(Value::prim(Prim::U8Type, [])));
+                let array_type = Value::prim(Prim::Array64Type, [len, elem_type]);
+
+                (
+                    core::Term::ArrayLit(file_range.into(), elem_exprs),
+                    Spanned::empty(Arc::new(array_type)),
+                )
             }
             // TODO: Stuck macros + unification like in Klister?
             Term::StringLiteral(range, _) => {
@@ -1970,6 +2345,8 @@ impl<'arena> Context<'arena> {
             (Gte(_), Some(((S32Type, []), (S32Type, [])))) => (S32Gte, BoolType),
             (Gte(_), Some(((S64Type, []), (S64Type, [])))) => (S64Gte, BoolType),
 
+            (Then(_), Some(((FormatType, []), (FormatType, [])))) => (FormatThen, FormatType),
+
             _ => {
                 self.push_message(Message::BinOpMismatchedTypes {
                     range: self.file_range(range),
@@ -2064,6 +2441,8 @@ impl<'arena> Context<'arena> {
             (Div(_), S32Type) => (S32Div, S32Type),
             (Div(_), S64Type) => (S64Div, S64Type),
 
+            (Then(_), FormatType) => (FormatThen, FormatType),
+
             _ => {
                 let (expr, synth_type) = self.synth_bin_op(range, lhs, op, rhs);
                 return self.coerce(range, expr, &synth_type, expected_type);
@@ -2317,6 +2696,9 @@ impl<'arena> Context<'arena> {
 
             // Default expression, defined if we arrive at a default case
             let default_branch;
+            // The default case is unreachable if all the values were covered
+            let is_default_reachable =
+                is_reachable && !is_exhaustive_const_match(&match_info.scrutinee.r#type, &branches);
 
             match self.check_pattern(pattern, &match_info.scrutinee.r#type) {
                 // Accumulate constant pattern. Search for it in the accumulated
@@ -2347,7 +2729,7 @@ impl<'arena> Context<'arena> {
                 // `core::Term::ConstMatch` binds a variable, so both
                 // the named and  placeholder patterns should bind this.
                 CheckedPattern::Binder(range, name) => {
-                    self.check_match_reachable(is_reachable, range);
+                    self.check_match_reachable(is_default_reachable, range);
 
                     // TODO: If we know this is an exhaustive match, bind the
                     // scrutinee to a let binding with the elaborated body, and
@@ -2359,7 +2741,7 @@ impl<'arena> Context<'arena> {
                     self.local_env.pop();
                 }
                 CheckedPattern::Placeholder(range) => {
-                    self.check_match_reachable(is_reachable, range);
+                    self.check_match_reachable(is_default_reachable, range);
 
                     (self.local_env).push_param(None, match_info.scrutinee.r#type.clone());
                     let default_expr = self.check(body_expr, &match_info.expected_type);
@@ -2388,10 +2770,11 @@ impl<'arena> Context<'arena> {
         // Finished all the constant patterns without encountering a default
         // case. This should have been an exhaustive match, so check to see if
         // all the cases were covered.
-        let default_expr = match match_info.scrutinee.r#type.match_prim_spine() {
+        let default_expr = if is_exhaustive_const_match(&match_info.scrutinee.r#type, &branches) {
             // No need for a default case if all the values were covered
-            Some((Prim::BoolType, [])) if branches.len() >= 2 => None,
-            _ => Some(self.elab_match_absurd(is_reachable, match_info)),
+            None
+        } else {
+            Some(self.elab_match_absurd(is_reachable, match_info))
         };
 
         core::Term::ConstMatch(
@@ -2433,6 +2816,19 @@ impl<'arena> Context<'arena> {
     }
 }
 
+/// Returns true if the branches of a constant match cover every value of the
+/// scrutinee type. The branches are assumed to be distinct.
+fn is_exhaustive_const_match<T>(scrutinee_type: &ArcValue<'_>, branches: &[(Const, T)]) -> bool {
+    let num_values: u64 = match scrutinee_type.match_prim_spine() {
+        Some((Prim::BoolType, [])) => 2,
+        Some((Prim::U8Type | Prim::S8Type, [])) => 1 << 8,
+        Some((Prim::U16Type | Prim::S16Type, [])) => 1 << 16,
+        _ => return false,
+    };
+
+    branches.len() as u64 >= num_values
+}
+
 trait FromStrRadix: Sized {
     fn from_str_radix(src: &str, radix: u32) -> Result<Self, std::num::ParseIntError>;
 }
@@ -2482,6 +2878,16 @@ struct MatchInfo<'arena> {
     expected_type: ArcValue<'arena>,
 }
 
+/// The number of arguments that have already been applied to the head of an
+/// application, through nested and parenthesised applications.
+fn applied_args(head_expr: &Term<'_, ByteRange>) -> usize {
+    match head_expr {
+        Term::Paren(_, head_expr) => applied_args(head_expr),
+        Term::App(_, head_expr, args) => args.len() + applied_args(head_expr),
+        _ => 0,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2491,4 +2897,43 @@ mod tests {
     fn checked_pattern_size() {
         assert_eq!(std::mem::size_of::<CheckedPattern>(), 32);
     }
+
+    #[test]
+    fn snapshot_restore() {
+        let scope = Scope::new();
+        let file_id = FileId::try_from(1).unwrap();
+        let range = FileRange::new(file_id, ByteRange::new(0, 0));
+        let mut context = Context::new(file_id, &scope, ItemEnv::new());
+
+        // Solutions from before the snapshot should be kept
+        let solved_meta = context.push_unsolved_type(MetaSource::PlaceholderType(range));
+        context
+            .unify(&solved_meta, &context.universe.clone())
+            .unwrap();
+        let meta = context.push_unsolved_type(MetaSource::PlaceholderType(range));
+        let meta_len = context.meta_env.exprs.len();
+        let snapshot = context.snapshot();
+
+        // Speculatively solve the existing metavariable, insert a new one,
+        // and report an error.
+        let source = String::from("(true : _) == (3 : U8)").try_into().unwrap();
+        let (term, messages) = Term::parse(&scope, &source);
+        assert!(messages.is_empty());
+        context.synth(&term);
+        context.unify(&meta, &context.bool_type.clone()).unwrap();
+        assert_ne!(context.meta_env.exprs.len(), meta_len);
+        assert!(!context.messages.is_empty());
+
+        context.restore(snapshot);
+
+        assert_eq!(context.meta_env.sources.len(), meta_len);
+        assert_eq!(context.meta_env.types.len(), meta_len);
+        assert_eq!(context.meta_env.exprs.len(), meta_len);
+        assert!(matches!(
+            context.meta_env.exprs.iter().collect::<Vec<_>>()[..],
+            [Some(_), None],
+        ));
+        assert_eq!(context.local_env.len(), EnvLen::new());
+        assert!(context.messages.is_empty());
+    }
 }
@@ -5,6 +5,8 @@
 //!
 //! The algorithm is as follows:
 //!
+//! 0. Collect the names of each item, reporting an error if an item name is
+//!    redefined. Only the first definition of each name is elaborated.
 //! 1. Traverse the terms within each module item and note dependencies on other
 //!    items.
 //!    * Names in scope are tracked so that when a local name shadows an item
@@ -18,6 +20,8 @@
 //!      re-enter an item already in the stack report an error indicating a
 //!      cycle has been detected.
 
+use std::collections::hash_map::Entry;
+
 use fxhash::{FxHashMap, FxHashSet};
 
 use crate::source::ByteRange;
@@ -33,23 +37,42 @@ pub fn elaboration_order(
     elab_context: &mut elaboration::Context,
     surface_module: &Module<'_, ByteRange>,
 ) -> Vec<usize> {
-    let item_names = item_names(surface_module);
+    let item_names = item_names(elab_context, surface_module);
     let item_deps = collect_item_dependencies(surface_module, &item_names);
 
     let context = ModuleOrderContext::new(elab_context);
     context.determine_order(surface_module.items, &item_names, &item_deps)
 }
 
-fn item_names(surface_module: &Module<'_, ByteRange>) -> FxHashMap<Symbol, usize> {
-    surface_module
-        .items
-        .iter()
-        .enumerate()
-        .filter_map(|(i, item)| match item {
-            Item::Def(item) => Some((item.label.1, i)),
-            Item::ReportedError(_) => None,
-        })
-        .collect()
+fn item_names(
+    elab_context: &mut elaboration::Context,
+    surface_module: &Module<'_, ByteRange>,
+) -> FxHashMap<Symbol, usize> {
+    let mut item_names = FxHashMap::default();
+
+    for (i, item) in surface_module.items.iter().enumerate() {
+        match item {
+            Item::Def(item) => match item_names.entry(item.label.1) {
+                Entry::Vacant(entry) => {
+                    entry.insert(i);
+                }
+                Entry::Occupied(entry) => {
+                    let original_range = match &surface_module.items[*entry.get()] {
+                        Item::Def(original_item) => original_item.label.0,
+                        Item::ReportedError(range) => *range,
+                    };
+                    elab_context.push_message(Message::DuplicateItemName {
+                        name: item.label.1,
+                        range: elab_context.file_range(item.label.0),
+                        original_range: elab_context.file_range(original_range),
+                    });
+                }
+            },
+            Item::ReportedError(_) => {}
+        }
+    }
+
+    item_names
 }
 
 fn collect_item_dependencies(
@@ -153,6 +176,13 @@ fn item_dependencies(
             if let Some(r#type) = item.r#type {
                 term_deps(r#type, item_names, local_names, &mut deps);
             }
+            for binding in item.where_bindings {
+                if let Some(r#type) = binding.r#type {
+                    term_deps(r#type, item_names, local_names, &mut deps);
+                }
+                term_deps(binding.expr, item_names, local_names, &mut deps);
+                push_pattern(&binding.pattern, local_names);
+            }
             term_deps(item.expr, item_names, local_names, &mut deps);
             local_names.truncate(initial_locals_names_len);
         }
@@ -276,6 +306,7 @@ fn term_deps(
         | Term::Placeholder(_)
         | Term::Universe(_)
         | Term::StringLiteral(_, _)
+        | Term::ByteStringLiteral(_, _)
         | Term::NumberLiteral(_, _)
         | Term::BooleanLiteral(_, _)
         | Term::ReportedError(_) => {}
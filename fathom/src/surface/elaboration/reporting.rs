@@ -34,6 +34,10 @@ pub enum Message {
         head_range: FileRange,
         head_type: String,
         arg_range: FileRange,
+        /// The number of arguments that were applied successfully.
+        expected_args: usize,
+        /// The number of arguments that were supplied in the application.
+        supplied_args: usize,
     },
     PlicityArgumentMismatch {
         head_range: FileRange,
@@ -83,6 +87,13 @@ pub enum Message {
     NonAsciiStringLiteral {
         invalid_range: FileRange,
     },
+    InvalidByteStringDigit {
+        invalid_range: FileRange,
+    },
+    OddLengthByteStringLiteral {
+        range: FileRange,
+        found_len: usize,
+    },
     StringLiteralNotSupported {
         range: FileRange,
         expected_type: String,
@@ -119,8 +130,8 @@ pub enum Message {
     /// A solution for a metavariable could not be found.
     UnsolvedMetaVar {
         source: MetaSource,
-        // TODO: add type
-        // type: Doc<_>,
+        /// The expected type of the metavariable, if it is known.
+        r#type: Option<String>,
     },
     HoleSolution {
         range: FileRange,
@@ -133,6 +144,12 @@ pub enum Message {
     CycleDetected {
         names: Vec<Symbol>,
     },
+    /// An item name was defined more than once in the same module.
+    DuplicateItemName {
+        name: Symbol,
+        range: FileRange,
+        original_range: FileRange,
+    },
     /// Core term lacked span information
     MissingSpan {
         range: FileRange,
@@ -183,13 +200,41 @@ impl Message {
                 head_range,
                 head_type,
                 arg_range,
-            } => Diagnostic::error()
-                .with_message("expression was applied to an unexpected argument")
-                .with_labels(vec![
-                    primary_label(arg_range).with_message("unexpected argument"),
-                    secondary_label(head_range)
-                        .with_message(format!("expression of type {head_type}")),
-                ]),
+                expected_args,
+                supplied_args,
+            } => {
+                let unexpected_args = supplied_args - expected_args;
+                let mut diagnostic = Diagnostic::error()
+                    .with_message(match unexpected_args {
+                        1 => "expression was applied to an unexpected argument".to_owned(),
+                        _ => format!(
+                            "expression was applied to {unexpected_args} unexpected arguments"
+                        ),
+                    })
+                    .with_labels(vec![
+                        primary_label(arg_range).with_message(match unexpected_args {
+                            1 => "unexpected argument",
+                            _ => "unexpected arguments",
+                        }),
+                        secondary_label(head_range)
+                            .with_message(format!("expression of type {head_type}")),
+                    ]);
+
+                if *expected_args > 0 {
+                    let arguments = |count| match count {
+                        1 => "1 argument".to_owned(),
+                        _ => format!("{count} arguments"),
+                    };
+                    diagnostic = diagnostic.with_notes(vec![format!(
+                        "this function takes {} but {} {} supplied",
+                        arguments(*expected_args),
+                        arguments(*supplied_args),
+                        if *supplied_args == 1 { "was" } else { "were" },
+                    )]);
+                }
+
+                diagnostic
+            }
             Message::PlicityArgumentMismatch {
                 head_range,
                 head_plicity,
@@ -343,6 +388,18 @@ impl Message {
                 .with_labels(vec![
                     primary_label(invalid_range).with_message("non-ASCII character")
                 ]),
+            Message::InvalidByteStringDigit { invalid_range } => Diagnostic::error()
+                .with_message("invalid hexadecimal digit found in byte string literal")
+                .with_labels(vec![
+                    primary_label(invalid_range).with_message("invalid hexadecimal digit")
+                ]),
+            Message::OddLengthByteStringLiteral { range, found_len } => Diagnostic::error()
+                .with_message("odd number of digits in byte string literal")
+                .with_labels(vec![primary_label(range)
+                    .with_message(format!("found {found_len} hexadecimal digits"))])
+                .with_notes(vec![
+                    "each byte must be written as a pair of hexadecimal digits".to_owned(),
+                ]),
             Message::StringLiteralNotSupported {
                 range,
                 expected_type,
@@ -398,12 +455,12 @@ impl Message {
                 expected,
                 error,
             } => {
-                use unification::{Error, RenameError, SpineError};
+                use unification::Error;
 
                 // TODO: Make these errors more user-friendly
                 match error {
                     Error::Mismatch => Diagnostic::error()
-                        .with_message("mismatched types")
+                        .with_message(error.to_string())
                         .with_labels(vec![primary_label(range).with_message(format!(
                             "type mismatch, expected `{expected}`, found `{found}`"
                         ))])
@@ -413,28 +470,9 @@ impl Message {
                         ]
                         .join("\n")]),
                     // TODO: reduce confusion around ‘problem spines’
-                    Error::Spine(error) => match error {
-                        SpineError::NonLinearSpine(_var) => Diagnostic::error()
-                            .with_message("variable appeared more than once in problem spine")
-                            .with_labels(vec![primary_label(range)]),
-                        SpineError::NonLocalFunApp => Diagnostic::error()
-                            .with_message("non-variable function application in problem spine")
-                            .with_labels(vec![primary_label(range)]),
-                        SpineError::RecordProj(_label) => Diagnostic::error()
-                            .with_message("record projection found in problem spine")
-                            .with_labels(vec![primary_label(range)]),
-                        SpineError::ConstMatch => Diagnostic::error()
-                            .with_message("constant match found in problem spine")
-                            .with_labels(vec![primary_label(range)]),
-                    },
-                    Error::Rename(error) => match error {
-                        RenameError::EscapingLocalVar(_var) => Diagnostic::error()
-                            .with_message("escaping local variable")
-                            .with_labels(vec![primary_label(range)]),
-                        RenameError::InfiniteSolution => Diagnostic::error()
-                            .with_message("infinite solution")
-                            .with_labels(vec![primary_label(range)]),
-                    },
+                    Error::Spine(_) | Error::Rename(_) => Diagnostic::error()
+                        .with_message(error.to_string())
+                        .with_labels(vec![primary_label(range)]),
                 }
             }
             Message::HoleSolution { range, name, expr } => {
@@ -447,7 +485,7 @@ impl Message {
                         "hole `?{name}` can be replaced with `{expr}`",
                     )])
             }
-            Message::UnsolvedMetaVar { source } => {
+            Message::UnsolvedMetaVar { source, r#type } => {
                 let (range, source_name) = match source {
                     MetaSource::ImplicitArg(range, _) => (range, "implicit argument"),
                     MetaSource::HoleExpr(range, _) => (range, "hole expression"),
@@ -464,11 +502,16 @@ impl Message {
                     MetaSource::ReportedErrorType(range) => (range, "error type"),
                 };
 
-                Diagnostic::error()
+                let diagnostic = Diagnostic::error()
                     .with_message(format!("failed to infer {source_name}"))
                     .with_labels(vec![
                         primary_label(range).with_message(format!("unsolved {source_name}"))
-                    ])
+                    ]);
+
+                match r#type {
+                    Some(r#type) => diagnostic.with_notes(vec![format!("hole has type `{type}`")]),
+                    None => diagnostic,
+                }
             }
             Message::CycleDetected { names } => {
                 let cycle = names.iter().map(|name| name.resolve()).join(" → ");
@@ -476,6 +519,19 @@ impl Message {
                     .with_message("cycle detected")
                     .with_notes(vec![cycle])
             }
+            Message::DuplicateItemName {
+                name,
+                range,
+                original_range,
+            } => Diagnostic::error()
+                .with_message(format!(
+                    "the name `{}` is defined multiple times",
+                    name.resolve()
+                ))
+                .with_labels(vec![
+                    primary_label(range).with_message("redefined here"),
+                    secondary_label(original_range).with_message("previous definition here"),
+                ]),
             Message::MissingSpan { range } => Diagnostic::bug()
                 .with_message("produced core term without span")
                 .with_labels(vec![primary_label(range)])
@@ -15,6 +15,7 @@
 //! [elaboration-zoo]: https://github.com/AndrasKovacs/elaboration-zoo/
 //! [elaboration-zoo/03-holes]: https://github.com/AndrasKovacs/elaboration-zoo/tree/master/03-holes
 
+use std::fmt;
 use std::sync::Arc;
 
 use scoped_arena::Scope;
@@ -50,6 +51,16 @@ pub enum Error {
     Rename(RenameError),
 }
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Mismatch => write!(f, "mismatched types"),
+            Error::Spine(error) => write!(f, "{error}"),
+            Error::Rename(error) => write!(f, "{error}"),
+        }
+    }
+}
+
 impl From<SpineError> for Error {
     fn from(error: SpineError) -> Error {
         Error::Spine(error)
@@ -115,6 +126,21 @@ pub enum SpineError {
     ConstMatch,
 }
 
+impl fmt::Display for SpineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpineError::NonLinearSpine(_) => {
+                write!(f, "variable appeared more than once in problem spine")
+            }
+            SpineError::NonLocalFunApp => {
+                write!(f, "non-variable function application in problem spine")
+            }
+            SpineError::RecordProj(_) => write!(f, "record projection found in problem spine"),
+            SpineError::ConstMatch => write!(f, "constant match found in problem spine"),
+        }
+    }
+}
+
 /// An error that occurred when renaming the solution.
 #[derive(Debug, Clone)]
 pub enum RenameError {
@@ -155,6 +181,15 @@ pub enum RenameError {
     InfiniteSolution,
 }
 
+impl fmt::Display for RenameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenameError::EscapingLocalVar(_) => write!(f, "escaping local variable"),
+            RenameError::InfiniteSolution => write!(f, "infinite solution"),
+        }
+    }
+}
+
 /// Unification context.
 pub struct Context<'arena, 'env> {
     /// Scoped arena for storing [renamed][Context::rename] terms.
@@ -169,6 +204,8 @@ pub struct Context<'arena, 'env> {
     local_exprs: EnvLen,
     /// Solutions for metavariables.
     meta_exprs: &'env mut SliceEnv<Option<ArcValue<'arena>>>,
+    /// Metavariables in the order that they were solved.
+    solved_metas: &'env mut Vec<Level>,
 }
 
 impl<'arena, 'env> Context<'arena, 'env> {
@@ -178,6 +215,7 @@ impl<'arena, 'env> Context<'arena, 'env> {
         item_exprs: &'env SliceEnv<ArcValue<'arena>>,
         local_exprs: EnvLen,
         meta_exprs: &'env mut SliceEnv<Option<ArcValue<'arena>>>,
+        solved_metas: &'env mut Vec<Level>,
     ) -> Context<'arena, 'env> {
         Context {
             scope,
@@ -185,6 +223,7 @@ impl<'arena, 'env> Context<'arena, 'env> {
             item_exprs,
             local_exprs,
             meta_exprs,
+            solved_metas,
         }
     }
 
@@ -298,6 +337,21 @@ impl<'arena, 'env> Context<'arena, 'env> {
 
             (Value::ConstLit(const0), Value::ConstLit(const1)) if const0 == const1 => Ok(()),
 
+            // Meta-meta cases
+            //
+            // Both values have different metavariables at their heads. The
+            // first metavariable might not be solvable in terms of the second
+            // (for example if the second is applied to local variables that
+            // are not in scope of the first), so if that fails we attempt to
+            // solve the second metavariable instead.
+            (
+                Value::Stuck(Head::MetaVar(var0), spine0),
+                Value::Stuck(Head::MetaVar(var1), spine1),
+            ) => match self.solve(*var0, spine0, &value1) {
+                Ok(()) => Ok(()),
+                Err(error) => self.solve(*var1, spine1, &value0).map_err(|_| error),
+            },
+
             // Meta-local cases
             //
             // One of the values has a metavariable at its head, so we
@@ -487,6 +541,7 @@ impl<'arena, 'env> Context<'arena, 'env> {
         let solution = self.elim_env().eval_env(&mut local_exprs).eval(&fun_term);
 
         self.meta_exprs.set_level(meta_var, Some(solution));
+        self.solved_metas.push(meta_var);
 
         Ok(())
     }
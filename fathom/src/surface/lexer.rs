@@ -5,7 +5,8 @@ use crate::files::FileId;
 use crate::source::{BytePos, ByteRange, ProgramSource};
 
 pub const KEYWORDS: &[&str] = &[
-    "def", "else", "false", "fun", "if", "let", "match", "overlap", "then", "true", "Type", "where",
+    "def", "else", "false", "fun", "if", "let", "match", "overlap", "then", "true", "type", "Type",
+    "where",
 ];
 
 pub fn is_keyword(word: &str) -> bool {
@@ -22,8 +23,13 @@ pub enum Token<'source> {
     Hole(&'source str),
     #[regex(r#""([^"\\]|\\.)*""#, |lex| &lex.slice()[1..(lex.slice().len() - 1)])]
     StringLiteral(&'source str),
+    #[regex(r#"0x"[^"]*""#, |lex| &lex.slice()[3..(lex.slice().len() - 1)])]
+    ByteStringLiteral(&'source str),
     #[regex(r"[+-]?[0-9][a-zA-Z0-9_]*")]
     NumberLiteral(&'source str),
+    #[regex(r"///\n", |_| "")]
+    #[regex(r"///[^/\n][^\n]*\n", |lex| &lex.slice()[3..(lex.slice().len() - 1)])]
+    DocComment(&'source str),
 
     #[token("def")]
     KeywordDef,
@@ -45,6 +51,8 @@ pub enum Token<'source> {
     KeywordThen,
     #[token("true")]
     KeywordTrue,
+    #[token("type")]
+    KeywordTypeAlias,
     #[token("Type")]
     KeywordType,
     #[token("where")]
@@ -66,6 +74,8 @@ pub enum Token<'source> {
     EqualsGreater,
     #[token(">=")]
     GreaterEquals,
+    #[token(">>")]
+    GreaterGreater,
     #[token(">")]
     Greater,
     #[token("<=")]
@@ -209,7 +219,7 @@ impl Error {
 pub fn tokens(
     source: &ProgramSource,
 ) -> impl Iterator<Item = Result<Spanned<Token<'_>, BytePos>, Error>> {
-    Token::lexer(source).spanned().map(move |(token, range)| {
+    let tokens = Token::lexer(source).spanned().map(move |(token, range)| {
         let start = range.start as BytePos;
         let end = range.end as BytePos;
         match token {
@@ -219,7 +229,26 @@ pub fn tokens(
             }),
             token => Ok((start, token, end)),
         }
-    })
+    });
+
+    // Doc comments are only kept if they precede an item, and are otherwise
+    // skipped like ordinary comments.
+    let mut documents_item = false;
+    let mut tokens = (tokens.collect::<Vec<_>>().into_iter().rev())
+        .filter(|token| match token {
+            Ok((_, Token::DocComment(_), _)) => documents_item,
+            Ok((_, Token::KeywordDef | Token::KeywordTypeAlias, _)) => {
+                documents_item = true;
+                true
+            }
+            _ => {
+                documents_item = false;
+                true
+            }
+        })
+        .collect::<Vec<_>>();
+    tokens.reverse();
+    tokens.into_iter()
 }
 
 impl<'source> Token<'source> {
@@ -228,7 +257,9 @@ impl<'source> Token<'source> {
             Token::Name(_) => "name",
             Token::Hole(_) => "hole",
             Token::StringLiteral(_) => "string literal",
+            Token::ByteStringLiteral(_) => "byte string literal",
             Token::NumberLiteral(_) => "number literal",
+            Token::DocComment(_) => "doc comment",
             Token::KeywordDef => "def",
             Token::KeywordElse => "else",
             Token::KeywordFalse => "false",
@@ -239,6 +270,7 @@ impl<'source> Token<'source> {
             Token::KeywordOverlap => "overlap",
             Token::KeywordThen => "then",
             Token::KeywordTrue => "true",
+            Token::KeywordTypeAlias => "type",
             Token::KeywordType => "Type",
             Token::KeywordWhere => "where",
             Token::At => "@",
@@ -266,6 +298,7 @@ impl<'source> Token<'source> {
             Token::BangEquals => "!=",
             Token::EqualsEquals => "==",
             Token::GreaterEquals => ">=",
+            Token::GreaterGreater => ">>",
             Token::Greater => ">",
             Token::LessEquals => "<=",
             Token::Less => "<",
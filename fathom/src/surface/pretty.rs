@@ -2,10 +2,12 @@ use pretty::{Doc, DocAllocator, DocPtr, RefDoc};
 use scoped_arena::Scope;
 
 use crate::surface::lexer::is_keyword;
-use crate::surface::{Arg, FormatField, Item, Module, Param, Pattern, Plicity, Term};
+use crate::surface::{
+    Arg, FormatField, Item, ItemKind, Module, Param, Pattern, Plicity, Term, WhereBinding,
+};
 use crate::symbol::Symbol;
 
-const INDENT: isize = 4;
+pub const INDENT: isize = 4;
 
 type DocBuilder<'arena> = pretty::DocBuilder<'arena, Context<'arena>>;
 
@@ -38,16 +40,16 @@ impl<'arena> Context<'arena> {
 
     fn item<Range>(&'arena self, item: &Item<'_, Range>) -> DocBuilder<'arena> {
         match item {
-            Item::Def(item) => self
-                .concat([
-                    self.text("def"),
+            Item::Def(item) => self.concat([
+                self.concat(item.doc_comment.iter().map(|line| {
+                    self.concat([self.text(format!("///{}", line.resolve())), self.hardline()])
+                })),
+                self.concat([
+                    self.text(item.kind.as_str()),
                     self.space(),
-                    match item.r#type {
-                        None => self.concat([
-                            self.ident(item.label.1),
-                            self.params(item.params),
-                            self.space(),
-                        ]),
+                    // The `Type` annotation of type definitions is implied
+                    match item.r#type.filter(|_| item.kind == ItemKind::Def) {
+                        None => self.concat([self.ident(item.label.1), self.params(item.params)]),
                         Some(r#type) => self.concat([
                             self.concat([
                                 self.ident(item.label.1),
@@ -64,13 +66,45 @@ impl<'arena> Context<'arena> {
                     self.text("="),
                     self.softline(),
                     self.term(item.expr),
+                    self.where_bindings(item.where_bindings),
                     self.text(";"),
                 ])
                 .group(),
+            ]),
             Item::ReportedError(_) => self.text("#error"),
         }
     }
 
+    fn where_bindings<Range>(
+        &'arena self,
+        bindings: &[WhereBinding<'_, Range>],
+    ) -> DocBuilder<'arena> {
+        if bindings.is_empty() {
+            return self.nil();
+        }
+
+        self.concat([
+            self.text(" where {"),
+            self.concat(bindings.iter().map(|binding| {
+                self.concat([
+                    self.hardline(),
+                    self.concat([
+                        self.ann_pattern(&binding.pattern, binding.r#type),
+                        self.space(),
+                        self.text("="),
+                        self.softline(),
+                        self.term(binding.expr),
+                        self.text(";"),
+                    ])
+                    .group(),
+                ])
+            }))
+            .nest(INDENT),
+            self.hardline(),
+            self.text("}"),
+        ])
+    }
+
     fn pattern<Range>(&'arena self, pattern: &Pattern<Range>) -> DocBuilder<'arena> {
         match pattern {
             Pattern::Placeholder(_) => self.text("_"),
@@ -283,6 +317,9 @@ impl<'arena> Context<'arena> {
             Term::StringLiteral(_, number) => {
                 self.concat([self.text("\""), self.symbol(*number), self.text("\"")])
             }
+            Term::ByteStringLiteral(_, bytes) => {
+                self.concat([self.text("0x\""), self.symbol(*bytes), self.text("\"")])
+            }
             Term::NumberLiteral(_, number) => self.symbol(*number),
             Term::BooleanLiteral(_, boolean) => match *boolean {
                 true => self.text("true"),